@@ -0,0 +1,217 @@
+use crate::*;
+use crate::far_mem::FarSlice;
+use crate::text::BDA_SEGMENT;
+use crate::xfer::XferBuf;
+
+pub const BDA_EQUIPMENT: u32 = 0x10;
+pub const BDA_EGA_MISC: u32 = 0x87;
+
+pub const STATE_INFO_SIZE: usize = 64;
+
+const MDA_STATUS: u16 = 0x3BA;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DisplayCode {
+    None,
+    Mda,
+    Cga,
+    EgaColor,
+    EgaMono,
+    Pga,
+    VgaMono,
+    VgaColor,
+    McgaDigitalColor,
+    McgaMono,
+    McgaColor,
+    Unknown(u8),
+}
+
+impl DisplayCode {
+    pub const fn from_u8(value: u8) -> DisplayCode {
+        match value {
+            0x00 => DisplayCode::None,
+            0x01 => DisplayCode::Mda,
+            0x02 => DisplayCode::Cga,
+            0x04 => DisplayCode::EgaColor,
+            0x05 => DisplayCode::EgaMono,
+            0x06 => DisplayCode::Pga,
+            0x07 => DisplayCode::VgaMono,
+            0x08 => DisplayCode::VgaColor,
+            0x0A => DisplayCode::McgaDigitalColor,
+            0x0B => DisplayCode::McgaMono,
+            0x0C => DisplayCode::McgaColor,
+            value => DisplayCode::Unknown(value),
+        }
+    }
+
+    pub const fn display(self) -> Option<Display> {
+        let (adapter, monitor) = match self {
+            DisplayCode::Mda => (Adapter::Mda, Monitor::Mono),
+            DisplayCode::Cga => (Adapter::Cga, Monitor::Color),
+            DisplayCode::EgaColor => (Adapter::Ega, Monitor::Color),
+            DisplayCode::EgaMono => (Adapter::Ega, Monitor::Mono),
+            DisplayCode::Pga => (Adapter::Pga, Monitor::Color),
+            DisplayCode::VgaMono => (Adapter::Vga, Monitor::Mono),
+            DisplayCode::VgaColor => (Adapter::Vga, Monitor::Color),
+            DisplayCode::McgaDigitalColor | DisplayCode::McgaColor => (Adapter::Mcga, Monitor::Color),
+            DisplayCode::McgaMono => (Adapter::Mcga, Monitor::Mono),
+            DisplayCode::None | DisplayCode::Unknown(_) => return None,
+        };
+        Some(Display { adapter, monitor })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Adapter {
+    Mda,
+    Hercules,
+    Cga,
+    Pga,
+    Ega,
+    Mcga,
+    Vga,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Monitor {
+    Mono,
+    Color,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Display {
+    pub adapter: Adapter,
+    pub monitor: Monitor,
+}
+
+/// Detects the active display adapter, using the most capable BIOS service available
+/// and falling back to the BIOS data area on machines older than the EGA.
+pub fn detect_adapter() -> Display {
+    if let Some(display) = int_10h_ax_1A00h_display_codes()
+        .and_then(|codes| DisplayCode::from_u8(codes.bl_active_code).display())
+    {
+        return display;
+    }
+    if let Some(ega) = int_10h_ah_12h_bl_10h_ega_info() {
+        // The EGA only drives the active display if the BIOS says so.
+        let bda = FarSlice::rm_segment(BDA_SEGMENT).ok();
+        let ega_active = bda.is_none_or(|bda| bda.read_u8(BDA_EGA_MISC) & 0x08 == 0);
+        if ega_active {
+            let monitor = if ega.bh_mono { Monitor::Mono } else { Monitor::Color };
+            return Display { adapter: Adapter::Ega, monitor };
+        }
+    }
+    detect_from_bda()
+}
+
+fn detect_from_bda() -> Display {
+    let equipment = match FarSlice::rm_segment(BDA_SEGMENT) {
+        Ok(bda) => bda.read_u16(BDA_EQUIPMENT),
+        Err(_) => 0x0020,
+    };
+    if (equipment >> 4) & 0x03 == 0x03 {
+        let adapter = if is_hercules() { Adapter::Hercules } else { Adapter::Mda };
+        Display { adapter, monitor: Monitor::Mono }
+    } else {
+        Display { adapter: Adapter::Cga, monitor: Monitor::Color }
+    }
+}
+
+/// The Hercules vertical retrace bit toggles on port 3BAh, where the MDA leaves it fixed.
+fn is_hercules() -> bool {
+    let first = unsafe { port_in_u8(MDA_STATUS) } & 0x80;
+    (0 .. 0x8000).any(|_| unsafe { port_in_u8(MDA_STATUS) } & 0x80 != first)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ScanLines {
+    Lines200,
+    Lines350,
+    Lines400,
+    Lines480,
+    Unknown(u8),
+}
+
+impl ScanLines {
+    pub const fn from_u8(value: u8) -> ScanLines {
+        match value {
+            0x00 => ScanLines::Lines200,
+            0x01 => ScanLines::Lines350,
+            0x02 => ScanLines::Lines400,
+            0x03 => ScanLines::Lines480,
+            value => ScanLines::Unknown(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VideoStateInfo {
+    pub functionality_table: RmFarPtr,
+    pub mode: u8,
+    pub cols: u16,
+    pub page_size: u16,
+    pub page_start: u16,
+    /// Column and row of the cursor on each page.
+    pub cursor_positions: [(u8, u8); 8],
+    pub cursor_end_line: u8,
+    pub cursor_start_line: u8,
+    pub active_page: u8,
+    pub crtc_port: u16,
+    pub mode_select: u8,
+    pub color_select: u8,
+    pub rows: u8,
+    pub char_height: u16,
+    pub active_display: DisplayCode,
+    pub alternate_display: DisplayCode,
+    pub colors: u16,
+    pub pages: u8,
+    pub scan_lines: ScanLines,
+    pub primary_font_block: u8,
+    pub secondary_font_block: u8,
+    pub misc_flags: u8,
+    pub memory_64k_minus_one: u8,
+    pub save_pointer_flags: u8,
+}
+
+impl VideoStateInfo {
+    /// Returns `None` on adapters without INT 10h AH=1Bh, which is VGA and MCGA only.
+    pub fn query(xfer: &XferBuf) -> Result<Option<VideoStateInfo>, DpmiErr> {
+        assert!(xfer.len() as usize >= STATE_INFO_SIZE, "transfer buffer is too small");
+        if !int_10h_ah_1Bh_state_info(xfer.ptr())?.al_supported {
+            return Ok(None);
+        }
+        let mut b = [0u8; STATE_INFO_SIZE];
+        xfer.slice().read(0, &mut b);
+        let u16_at = |offset: usize| u16::from_le_bytes([b[offset], b[offset + 1]]);
+        let mut cursor_positions = [(0, 0); 8];
+        for (page, position) in cursor_positions.iter_mut().enumerate() {
+            *position = (b[0x0B + 2 * page], b[0x0C + 2 * page]);
+        }
+        Ok(Some(VideoStateInfo {
+            functionality_table: RmFarPtr::new(u16_at(0x02), u16_at(0x00)),
+            mode: b[0x04],
+            cols: u16_at(0x05),
+            page_size: u16_at(0x07),
+            page_start: u16_at(0x09),
+            cursor_positions,
+            cursor_end_line: b[0x1B],
+            cursor_start_line: b[0x1C],
+            active_page: b[0x1D],
+            crtc_port: u16_at(0x1E),
+            mode_select: b[0x20],
+            color_select: b[0x21],
+            rows: b[0x22],
+            char_height: u16_at(0x23),
+            active_display: DisplayCode::from_u8(b[0x25]),
+            alternate_display: DisplayCode::from_u8(b[0x26]),
+            colors: u16_at(0x27),
+            pages: b[0x29],
+            scan_lines: ScanLines::from_u8(b[0x2A]),
+            primary_font_block: b[0x2B],
+            secondary_font_block: b[0x2C],
+            misc_flags: b[0x2D],
+            memory_64k_minus_one: b[0x31],
+            save_pointer_flags: b[0x32],
+        }))
+    }
+}
@@ -0,0 +1,144 @@
+use core::cmp::{max, min};
+use crate::*;
+use crate::text::{Cell, TextScreen};
+use crate::vga::wait_vretrace;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+struct Rect {
+    left: u16,
+    top: u16,
+    right: u16,
+    bottom: u16,
+}
+
+impl Rect {
+    fn union(self, other: Rect) -> Rect {
+        Rect {
+            left: min(self.left, other.left),
+            top: min(self.top, other.top),
+            right: max(self.right, other.right),
+            bottom: max(self.bottom, other.bottom),
+        }
+    }
+}
+
+pub struct Compositor<'a> {
+    screen: TextScreen,
+    back: &'a mut [Cell],
+    front: &'a mut [Cell],
+    dirty: Option<Rect>,
+    vsync: bool,
+}
+
+impl<'a> Compositor<'a> {
+    pub fn new(screen: TextScreen, back: &'a mut [Cell], front: &'a mut [Cell]) -> Compositor<'a> {
+        let len = screen.cols() as usize * screen.rows() as usize;
+        assert!(back.len() >= len && front.len() >= len, "compositor buffer is too small");
+        let back = &mut back[.. len];
+        let front = &mut front[.. len];
+        for (y, row) in front.chunks_mut(screen.cols() as usize).enumerate() {
+            screen.read_row(0, y as u16, row);
+        }
+        back.copy_from_slice(front);
+        Compositor { screen, back, front, dirty: None, vsync: false }
+    }
+
+    pub fn screen(&self) -> &TextScreen { &self.screen }
+
+    pub fn cols(&self) -> u16 { self.screen.cols() }
+
+    pub fn rows(&self) -> u16 { self.screen.rows() }
+
+    pub fn vsync(&self) -> bool { self.vsync }
+
+    pub fn set_vsync(&mut self, vsync: bool) { self.vsync = vsync; }
+
+    fn mark_dirty(&mut self, x: u16, y: u16, width: u16, height: u16) {
+        if width == 0 || height == 0 { return; }
+        let rect = Rect { left: x, top: y, right: x + width, bottom: y + height };
+        self.dirty = Some(self.dirty.map_or(rect, |dirty| dirty.union(rect)));
+    }
+
+    pub fn invalidate(&mut self) {
+        for cell in self.front.iter_mut() {
+            *cell = Cell::from_u16(!cell.to_u16());
+        }
+        self.mark_dirty(0, 0, self.cols(), self.rows());
+    }
+
+    pub fn buf(&self) -> &[Cell] { self.back }
+
+    pub fn buf_mut(&mut self) -> &mut [Cell] {
+        self.mark_dirty(0, 0, self.cols(), self.rows());
+        self.back
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        assert!(x < self.cols() && y < self.rows(), "compositor position out of bounds");
+        y as usize * self.cols() as usize + x as usize
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Cell {
+        self.back[self.index(x, y)]
+    }
+
+    pub fn set_cell(&mut self, x: u16, y: u16, cell: Cell) {
+        let index = self.index(x, y);
+        self.back[index] = cell;
+        self.mark_dirty(x, y, 1, 1);
+    }
+
+    pub fn write_str(&mut self, x: u16, y: u16, s: &[u8], attr: Attr) {
+        let len = min(s.len(), self.cols().saturating_sub(x) as usize);
+        if len == 0 { return; }
+        let index = self.index(x, y);
+        for (cell, &ch) in self.back[index .. index + len].iter_mut().zip(s) {
+            *cell = Cell { ch, attr };
+        }
+        self.mark_dirty(x, y, len as u16, 1);
+    }
+
+    pub fn fill_rect(&mut self, x: u16, y: u16, width: u16, height: u16, cell: Cell) {
+        let width = min(width, self.cols().saturating_sub(x));
+        let height = min(height, self.rows().saturating_sub(y));
+        if width == 0 || height == 0 { return; }
+        for row in y .. y + height {
+            let index = self.index(x, row);
+            self.back[index .. index + width as usize].fill(cell);
+        }
+        self.mark_dirty(x, y, width, height);
+    }
+
+    pub fn clear(&mut self, cell: Cell) {
+        self.back.fill(cell);
+        self.mark_dirty(0, 0, self.cols(), self.rows());
+    }
+
+    pub fn flush(&mut self) -> usize {
+        let Some(dirty) = self.dirty.take() else { return 0; };
+        if self.vsync {
+            wait_vretrace();
+        }
+        let cols = self.cols() as usize;
+        let mut written = 0;
+        for y in dirty.top .. dirty.bottom {
+            let row = y as usize * cols;
+            let mut x = dirty.left as usize;
+            while x < dirty.right as usize {
+                if self.back[row + x] == self.front[row + x] {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < dirty.right as usize && self.back[row + x] != self.front[row + x] {
+                    x += 1;
+                }
+                let run = &self.back[row + start .. row + x];
+                self.screen.write_row(start as u16, y, run);
+                self.front[row + start .. row + x].copy_from_slice(run);
+                written += x - start;
+            }
+        }
+        written
+    }
+}
@@ -0,0 +1,326 @@
+use core::cmp::{Ordering, min};
+use crate::*;
+use crate::date_time::{DosDate, DosTime};
+use crate::far_mem::FarSlice;
+use crate::xfer::{RmCallErr, XferBuf};
+
+pub const COUNTRY_INFO_SIZE: usize = 34;
+
+/// Selects the active code page or country in the AX=65xxh queries.
+pub const ACTIVE: u16 = 0xFFFF;
+
+const EXT_COUNTRY_INFO: u8 = 0x01;
+const UPPERCASE_TABLE: u8 = 0x02;
+const FILENAME_UPPERCASE_TABLE: u8 = 0x04;
+const COLLATING_TABLE: u8 = 0x06;
+const DBCS_TABLE: u8 = 0x07;
+
+/// Info ID byte and the far pointer DOS returns for tables.
+const TABLE_PTR_SIZE: u16 = 5;
+/// Info ID, size, country and code page ahead of the country information.
+const EXT_COUNTRY_HEADER_SIZE: usize = 7;
+
+const MAX_DBCS_RANGES: usize = 8;
+
+/// The most decimals a `u64` scale can hold with room to spare.
+const MAX_DECIMALS: u8 = 18;
+
+const CURRENCY_FOLLOWS: u8 = 0x01;
+const CURRENCY_SPACE: u8 = 0x02;
+const CURRENCY_REPLACES_DECIMAL: u8 = 0x04;
+const TIME_24H: u8 = 0x01;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DateFormat {
+    MonthDayYear,
+    DayMonthYear,
+    YearMonthDay,
+}
+
+/// Bytes in the DOS code page, ready for the text functions.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Formatted {
+    bytes: [u8; 64],
+    len: u8,
+}
+
+impl Formatted {
+    const fn new() -> Formatted { Formatted { bytes: [0; 64], len: 0 } }
+
+    fn push(&mut self, byte: u8) {
+        self.bytes[self.len as usize] = byte;
+        self.len += 1;
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push(byte);
+        }
+    }
+
+    /// Writes `value` with at least `min_digits` digits, grouped by threes with `separator` if any.
+    fn push_number(&mut self, value: u64, min_digits: u8, separator: Option<u8>) {
+        let mut digits = [0u8; 20];
+        let mut count = 0;
+        let mut rest = value;
+        while rest != 0 || count < min_digits.max(1) as usize {
+            digits[count] = b'0' + (rest % 10) as u8;
+            rest /= 10;
+            count += 1;
+        }
+        for i in (0 .. count).rev() {
+            self.push(digits[i]);
+            if i != 0 && i % 3 == 0 {
+                if let Some(separator) = separator { self.push(separator); }
+            }
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] { &self.bytes[.. self.len as usize] }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct CountryInfo {
+    pub country: u16,
+    /// Only known when read with [`CountryInfo::query_ext`].
+    pub code_page: Option<u16>,
+    pub date_format: DateFormat,
+    currency_symbol: [u8; 5],
+    pub thousands_separator: u8,
+    pub decimal_separator: u8,
+    pub date_separator: u8,
+    pub time_separator: u8,
+    pub list_separator: u8,
+    pub currency_follows: bool,
+    pub currency_space: bool,
+    /// The symbol stands in for the decimal separator, as in `12$50`.
+    pub currency_replaces_decimal: bool,
+    pub currency_digits: u8,
+    pub clock_24h: bool,
+    /// Far procedure mapping AL to uppercase for characters from 80h.
+    pub case_map: RmFarPtr,
+}
+
+impl CountryInfo {
+    fn parse(country: u16, code_page: Option<u16>, info: &[u8; COUNTRY_INFO_SIZE]) -> CountryInfo {
+        let currency_format = info[0x0F];
+        CountryInfo {
+            country,
+            code_page,
+            date_format: match u16::from_le_bytes([info[0], info[1]]) {
+                1 => DateFormat::DayMonthYear,
+                2 => DateFormat::YearMonthDay,
+                _ => DateFormat::MonthDayYear,
+            },
+            currency_symbol: [info[2], info[3], info[4], info[5], info[6]],
+            thousands_separator: info[0x07],
+            decimal_separator: info[0x09],
+            date_separator: info[0x0B],
+            time_separator: info[0x0D],
+            list_separator: info[0x16],
+            currency_follows: currency_format & CURRENCY_FOLLOWS != 0,
+            currency_space: currency_format & CURRENCY_SPACE != 0,
+            currency_replaces_decimal: currency_format & CURRENCY_REPLACES_DECIMAL != 0,
+            currency_digits: min(info[0x10], MAX_DECIMALS),
+            clock_24h: info[0x11] & TIME_24H != 0,
+            case_map: RmFarPtr::from_u32(u32::from_le_bytes([info[0x12], info[0x13], info[0x14], info[0x15]])),
+        }
+    }
+
+    /// Reads the current country with INT 21h AX=3800h.
+    pub fn query(xfer: &XferBuf) -> Result<CountryInfo, RmCallErr> {
+        assert!(xfer.len() as usize >= COUNTRY_INFO_SIZE, "transfer buffer is too small");
+        let country = xfer.int_21h_ax_3800h_country_info()?.bx_country;
+        let mut info = [0; COUNTRY_INFO_SIZE];
+        xfer.slice().read(0, &mut info);
+        Ok(CountryInfo::parse(country, None, &info))
+    }
+
+    /// Reads any country and code page DOS has information for, or [`ACTIVE`] ones, with AX=6501h.
+    pub fn query_ext(xfer: &XferBuf, code_page: u16, country: u16) -> Result<CountryInfo, RmCallErr> {
+        const SIZE: usize = EXT_COUNTRY_HEADER_SIZE + COUNTRY_INFO_SIZE;
+        xfer.int_21h_ah_65h_ext_country_info(EXT_COUNTRY_INFO, code_page, country, SIZE as u16)?;
+        let mut buf = [0; SIZE];
+        xfer.slice().read(0, &mut buf);
+        let mut info = [0; COUNTRY_INFO_SIZE];
+        info.copy_from_slice(&buf[EXT_COUNTRY_HEADER_SIZE ..]);
+        let country = u16::from_le_bytes([buf[3], buf[4]]);
+        let code_page = u16::from_le_bytes([buf[5], buf[6]]);
+        Ok(CountryInfo::parse(country, Some(code_page), &info))
+    }
+
+    pub fn currency_symbol(&self) -> &[u8] {
+        let len = self.currency_symbol.iter().position(|&b| b == 0).unwrap_or(self.currency_symbol.len());
+        &self.currency_symbol[.. len]
+    }
+
+    pub fn format_date(&self, date: DosDate) -> Formatted {
+        let mut s = Formatted::new();
+        let (year, month, day) = (date.year() as u64, date.month() as u64, date.day() as u64);
+        let fields = match self.date_format {
+            DateFormat::MonthDayYear => [(month, 2), (day, 2), (year, 4)],
+            DateFormat::DayMonthYear => [(day, 2), (month, 2), (year, 4)],
+            DateFormat::YearMonthDay => [(year, 4), (month, 2), (day, 2)],
+        };
+        for (i, (value, digits)) in fields.into_iter().enumerate() {
+            if i != 0 { s.push(self.date_separator); }
+            s.push_number(value, digits, None);
+        }
+        s
+    }
+
+    /// Uses the country's 12 or 24 hour clock, with DOS's `a` and `p` suffixes for the former.
+    pub fn format_time(&self, time: DosTime, seconds: bool) -> Formatted {
+        let mut s = Formatted::new();
+        let hour = time.hour();
+        if self.clock_24h {
+            s.push_number(hour as u64, 2, None);
+        } else {
+            s.push_number(match hour % 12 { 0 => 12, h => h } as u64, 1, None);
+        }
+        s.push(self.time_separator);
+        s.push_number(time.minute() as u64, 2, None);
+        if seconds {
+            s.push(self.time_separator);
+            s.push_number(time.second() as u64, 2, None);
+        }
+        if !self.clock_24h {
+            s.push(if hour < 12 { b'a' } else { b'p' });
+        }
+        s
+    }
+
+    /// Formats `value / 10^decimals` with the country's separators.
+    pub fn format_decimal(&self, value: i64, decimals: u8) -> Formatted {
+        let mut s = Formatted::new();
+        if value < 0 { s.push(b'-'); }
+        self.push_decimal(&mut s, value.unsigned_abs(), decimals);
+        s
+    }
+
+    /// Formats an amount in the currency's smallest unit, `value / 10^currency_digits`.
+    pub fn format_currency(&self, value: i64) -> Formatted {
+        let mut s = Formatted::new();
+        if value < 0 { s.push(b'-'); }
+        let value = value.unsigned_abs();
+        if self.currency_replaces_decimal {
+            let scale = 10u64.pow(self.currency_digits as u32);
+            let (units, fraction) = (value / scale, value % scale);
+            s.push_number(units, 1, Some(self.thousands_separator));
+            s.push_bytes(self.currency_symbol());
+            if self.currency_digits != 0 {
+                s.push_number(fraction, self.currency_digits, None);
+            }
+            return s;
+        }
+        if !self.currency_follows {
+            s.push_bytes(self.currency_symbol());
+            if self.currency_space { s.push(b' '); }
+        }
+        self.push_decimal(&mut s, value, self.currency_digits);
+        if self.currency_follows {
+            if self.currency_space { s.push(b' '); }
+            s.push_bytes(self.currency_symbol());
+        }
+        s
+    }
+
+    fn push_decimal(&self, s: &mut Formatted, value: u64, decimals: u8) {
+        assert!(decimals <= MAX_DECIMALS);
+        let scale = 10u64.pow(decimals as u32);
+        s.push_number(value / scale, 1, Some(self.thousands_separator));
+        if decimals != 0 {
+            s.push(self.decimal_separator);
+            s.push_number(value % scale, decimals, None);
+        }
+    }
+}
+
+/// Reads the `size`-prefixed table DOS points at after the info ID byte.
+fn read_table(xfer: &XferBuf, info_id: u8, code_page: u16, country: u16, dest: &mut [u8]) -> Result<u16, RmCallErr> {
+    xfer.int_21h_ah_65h_ext_country_info(info_id, code_page, country, TABLE_PTR_SIZE)?;
+    let mut ptr = [0; TABLE_PTR_SIZE as usize];
+    xfer.slice().read(0, &mut ptr);
+    let table = RmFarPtr::from_u32(u32::from_le_bytes([ptr[1], ptr[2], ptr[3], ptr[4]]));
+    let mem = FarSlice::rm_segment(table.segment)?;
+    let size = mem.read_u16(table.offset as u32);
+    let len = min(min(size as usize, dest.len()), (0x10000 - table.offset as usize).saturating_sub(2));
+    mem.read(table.offset as u32 + 2, &mut dest[.. len]);
+    Ok(size)
+}
+
+/// Uppercase forms of characters 80h to FFh.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct UppercaseTable([u8; 128]);
+
+impl UppercaseTable {
+    /// The table for text, from AX=6502h.
+    pub fn query(xfer: &XferBuf, code_page: u16, country: u16) -> Result<UppercaseTable, RmCallErr> {
+        let mut table = [0; 128];
+        read_table(xfer, UPPERCASE_TABLE, code_page, country, &mut table)?;
+        Ok(UppercaseTable(table))
+    }
+
+    /// The table for file names, from AX=6504h.
+    pub fn query_filename(xfer: &XferBuf, code_page: u16, country: u16) -> Result<UppercaseTable, RmCallErr> {
+        let mut table = [0; 128];
+        read_table(xfer, FILENAME_UPPERCASE_TABLE, code_page, country, &mut table)?;
+        Ok(UppercaseTable(table))
+    }
+
+    pub fn to_upper(&self, ch: u8) -> u8 {
+        if ch < 0x80 { ch.to_ascii_uppercase() } else { self.0[(ch - 0x80) as usize] }
+    }
+
+    pub fn make_upper(&self, s: &mut [u8]) {
+        for ch in s {
+            *ch = self.to_upper(*ch);
+        }
+    }
+}
+
+/// Sort weights of all 256 characters.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct CollatingTable([u8; 256]);
+
+impl CollatingTable {
+    pub fn query(xfer: &XferBuf, code_page: u16, country: u16) -> Result<CollatingTable, RmCallErr> {
+        let mut table = [0; 256];
+        read_table(xfer, COLLATING_TABLE, code_page, country, &mut table)?;
+        Ok(CollatingTable(table))
+    }
+
+    pub fn weight(&self, ch: u8) -> u8 { self.0[ch as usize] }
+
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        a.iter().map(|&ch| self.weight(ch)).cmp(b.iter().map(|&ch| self.weight(ch)))
+    }
+}
+
+/// Ranges of lead bytes that start a double-byte character.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct DbcsLeadBytes {
+    ranges: [(u8, u8); MAX_DBCS_RANGES],
+    len: u8,
+}
+
+impl DbcsLeadBytes {
+    /// Reads the AX=6507h table, which is empty for single-byte code pages.
+    pub fn query(xfer: &XferBuf, code_page: u16, country: u16) -> Result<DbcsLeadBytes, RmCallErr> {
+        let mut table = [0; MAX_DBCS_RANGES * 2];
+        let size = read_table(xfer, DBCS_TABLE, code_page, country, &mut table)?;
+        let mut lead_bytes = DbcsLeadBytes { ranges: [(0, 0); MAX_DBCS_RANGES], len: 0 };
+        for pair in table[.. min(size as usize, table.len())].chunks_exact(2) {
+            if pair == [0, 0] { break; }
+            lead_bytes.ranges[lead_bytes.len as usize] = (pair[0], pair[1]);
+            lead_bytes.len += 1;
+        }
+        Ok(lead_bytes)
+    }
+
+    pub fn ranges(&self) -> &[(u8, u8)] { &self.ranges[.. self.len as usize] }
+
+    pub fn is_lead_byte(&self, byte: u8) -> bool {
+        self.ranges().iter().any(|&(first, last)| (first ..= last).contains(&byte))
+    }
+}
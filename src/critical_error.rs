@@ -0,0 +1,200 @@
+#[cfg(dos)]
+use core::arch::naked_asm;
+use core::mem::transmute;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use crate::*;
+use crate::ctrl_break::cleanup;
+use crate::far_mem::FarSlice;
+use crate::isr::*;
+
+pub const CRITICAL_ERROR_VECTOR: u8 = 0x24;
+
+const AH_WRITE: u8 = 0x01;
+const AH_IGNORE_ALLOWED: u8 = 0x20;
+const AH_RETRY_ALLOWED: u8 = 0x10;
+const AH_FAIL_ALLOWED: u8 = 0x08;
+const AH_NOT_DISK: u8 = 0x80;
+
+const DEVICE_ATTR: u32 = 0x04;
+const DEVICE_ATTR_CHAR: u16 = 0x8000;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DiskArea {
+    Dos,
+    Fat,
+    RootDir,
+    Data,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum CriticalAction {
+    Ignore = 0,
+    Retry = 1,
+    /// Terminates the program through INT 23h. Crate hooks and the saved video state
+    /// are restored first.
+    Abort = 2,
+    Fail = 3,
+}
+
+#[derive(Debug, Clone)]
+pub struct CriticalError {
+    /// Drive number with 0 for A:, for disk errors only.
+    pub drive: Option<u8>,
+    pub write: bool,
+    pub area: DiskArea,
+    pub ignore_allowed: bool,
+    pub retry_allowed: bool,
+    pub fail_allowed: bool,
+    pub device_header: RmFarPtr,
+    /// One of `DOS_ERR_DISK_WRITE_PROTECTED ..= DOS_ERR_GENERAL_FAILURE`.
+    pub error: u8,
+}
+
+impl CriticalError {
+    pub fn from_regs(ah_flags: u8, al_drive: u8, di_error: u16, bp_si_device_header: RmFarPtr) -> CriticalError {
+        CriticalError {
+            drive: if ah_flags & AH_NOT_DISK == 0 { Some(al_drive) } else { None },
+            write: ah_flags & AH_WRITE != 0,
+            area: match (ah_flags >> 1) & 0x03 {
+                0 => DiskArea::Dos,
+                1 => DiskArea::Fat,
+                2 => DiskArea::RootDir,
+                _ => DiskArea::Data,
+            },
+            ignore_allowed: ah_flags & AH_IGNORE_ALLOWED != 0,
+            retry_allowed: ah_flags & AH_RETRY_ALLOWED != 0,
+            fail_allowed: ah_flags & AH_FAIL_ALLOWED != 0,
+            device_header: bp_si_device_header,
+            error: match di_error & 0xFF {
+                code @ 0x00 ..= 0x0C => DOS_ERR_DISK_WRITE_PROTECTED + code as u8,
+                _ => DOS_ERR_GENERAL_FAILURE,
+            },
+        }
+    }
+
+    pub fn is_disk_error(&self) -> bool { self.drive.is_some() }
+
+    /// Reads the device attributes: a character device, or a block device whose FAT image failed.
+    pub fn is_char_device(&self) -> Result<bool, AxErr> {
+        let header = FarSlice::rm_segment(self.device_header.segment)?;
+        Ok(header.read_u16(self.device_header.offset as u32 + DEVICE_ATTR) & DEVICE_ATTR_CHAR != 0)
+    }
+
+    pub fn device_name(&self) -> Result<[u8; 8], AxErr> {
+        let header = FarSlice::rm_segment(self.device_header.segment)?;
+        let mut name = [0; 8];
+        header.read(self.device_header.offset as u32 + 0x0A, &mut name);
+        Ok(name)
+    }
+}
+
+/// Runs inside DOS: it may only use INT 21h functions 01h to 0Ch, 30h and 59h, plus BIOS services.
+pub type CriticalErrorHandler = fn(&CriticalError) -> CriticalAction;
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(null_mut());
+static OLD_HANDLER: IsrChain = IsrChain::new();
+static STACK: IsrStack = IsrStack::new();
+
+extern "C" fn critical_error_isr(ax: u32, di: u32, bp: u32, si: u32) -> u32 {
+    let handler = HANDLER.load(Ordering::Acquire);
+    if handler.is_null() { return CriticalAction::Fail as u32; }
+    let handler: CriticalErrorHandler = unsafe { transmute::<*mut (), CriticalErrorHandler>(handler) };
+    let error = CriticalError::from_regs((ax >> 8) as u8, ax as u8, di as u16, RmFarPtr::new(bp as u16, si as u16));
+    let action = handler(&error);
+    if action == CriticalAction::Abort {
+        cleanup();
+    }
+    action as u32
+}
+
+#[cfg(not(dos))]
+unsafe extern "C" fn critical_error_entry() {
+    let _ = critical_error_isr;
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[unsafe(naked)]
+unsafe extern "C" fn critical_error_entry() {
+    naked_asm!(
+        "push ds",
+        "push es",
+        "push ebx",
+        "push ecx",
+        "push edx",
+        "push esi",
+        "push edi",
+        "push ebp",
+        "mov ecx, esp",
+        "mov edx, ss",
+        "mov ds, word ptr cs:[{ds}]",
+        "mov es, word ptr cs:[{ds}]",
+        "mov ss, word ptr cs:[{ds}]",
+        "lea esp, [{stack} + {stack_size}]",
+        "push edx",
+        "push ecx",
+        "cld",
+        "push esi",
+        "push ebp",
+        "push edi",
+        "push eax",
+        "call {handler}",
+        "add esp, 16",
+        "pop ecx",
+        "pop edx",
+        "mov ss, dx",
+        "mov esp, ecx",
+        "pop ebp",
+        "pop edi",
+        "pop esi",
+        "pop edx",
+        "pop ecx",
+        "pop ebx",
+        "pop es",
+        "pop ds",
+        "iretd",
+        ds = sym ISR_DS,
+        stack = sym STACK,
+        stack_size = const ISR_STACK_SIZE,
+        handler = sym critical_error_isr,
+    );
+}
+
+/// Owns the INT 24h vector while alive. Only one handler can be installed at a time.
+pub struct CriticalErrorHook(());
+
+impl CriticalErrorHook {
+    pub fn install(handler: CriticalErrorHandler) -> Result<CriticalErrorHook, InstallErr> {
+        isr_init()?;
+        if INSTALLED.swap(true, Ordering::Acquire) { return Err(InstallErr::AlreadyInstalled); }
+        HANDLER.store(handler as *mut (), Ordering::Release);
+        lock_data(&HANDLER);
+        lock_data(&STACK);
+        OLD_HANDLER.set(int_21h_ah_35h_get_int(CRITICAL_ERROR_VECTOR).es_ebx_int_handler);
+        let entry = PmFarPtr::new(code_selector(), critical_error_entry as *const () as usize as u32);
+        int_21h_ah_25h_set_int(CRITICAL_ERROR_VECTOR, entry);
+        Ok(CriticalErrorHook(()))
+    }
+
+    pub fn set_handler(&self, handler: CriticalErrorHandler) {
+        HANDLER.store(handler as *mut (), Ordering::Release);
+    }
+}
+
+/// Puts the original INT 24h handler back if the hook is installed.
+pub(crate) fn uninstall() {
+    if INSTALLED.load(Ordering::Acquire) {
+        int_21h_ah_25h_set_int(CRITICAL_ERROR_VECTOR, OLD_HANDLER.get());
+        HANDLER.store(null_mut(), Ordering::Release);
+        INSTALLED.store(false, Ordering::Release);
+    }
+}
+
+impl Drop for CriticalErrorHook {
+    fn drop(&mut self) {
+        uninstall();
+    }
+}
@@ -0,0 +1,135 @@
+use core::mem::transmute;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU16, Ordering};
+use crate::*;
+use crate::isr::*;
+use crate::video_state::restore_video_state;
+
+pub const CTRL_C_VECTOR: u8 = 0x23;
+pub const CTRL_BREAK_VECTOR: u8 = 0x1B;
+
+const NO_PENDING_EXIT: u16 = 0xFFFF;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BreakSource {
+    /// DOS noticed Ctrl-C or Ctrl-Break while checking for it and raised INT 23h.
+    CtrlC,
+    /// The BIOS keyboard handler raised INT 1Bh for Ctrl-Break, from inside IRQ1.
+    CtrlBreak,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BreakAction {
+    Ignore,
+    Terminate(u8),
+}
+
+pub type BreakHandler = fn(BreakSource) -> BreakAction;
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(null_mut());
+static PENDING_EXIT: AtomicU16 = AtomicU16::new(NO_PENDING_EXIT);
+static OLD_CTRL_C: IsrChain = IsrChain::new();
+static OLD_CTRL_BREAK: IsrChain = IsrChain::new();
+static CTRL_C_STACK: IsrStack = IsrStack::new();
+static CTRL_BREAK_STACK: IsrStack = IsrStack::new();
+
+fn handle(source: BreakSource) -> BreakAction {
+    let handler = HANDLER.load(Ordering::Acquire);
+    if handler.is_null() { return BreakAction::Ignore; }
+    let handler: BreakHandler = unsafe { transmute::<*mut (), BreakHandler>(handler) };
+    handler(source)
+}
+
+extern "C" fn ctrl_c_isr() -> u32 {
+    let pending = PENDING_EXIT.swap(NO_PENDING_EXIT, Ordering::Relaxed);
+    let action = if pending != NO_PENDING_EXIT {
+        BreakAction::Terminate(pending as u8)
+    } else {
+        handle(BreakSource::CtrlC)
+    };
+    match action {
+        BreakAction::Ignore => 0,
+        BreakAction::Terminate(al_exit_code) => terminate(al_exit_code),
+    }
+}
+
+extern "C" fn ctrl_break_isr() -> u32 {
+    match handle(BreakSource::CtrlBreak) {
+        BreakAction::Ignore => 0,
+        BreakAction::Terminate(al_exit_code) => {
+            // DOS can not be entered from IRQ1, so let the old handler flag the break
+            // and terminate from the INT 23h that DOS raises at its next check.
+            PENDING_EXIT.store(al_exit_code as u16, Ordering::Relaxed);
+            1
+        },
+    }
+}
+
+isr_entry!(ctrl_c_entry, ctrl_c_isr, CTRL_C_STACK, OLD_CTRL_C);
+isr_entry!(ctrl_break_entry, ctrl_break_isr, CTRL_BREAK_STACK, OLD_CTRL_BREAK);
+
+/// Owns the INT 23h and INT 1Bh vectors while alive. Only one hook can be installed at a time.
+///
+/// Hosts reflect real-mode INT 23h to protected mode, but not all of them reflect INT 1Bh;
+/// where they don't, Ctrl-Break still reaches the handler as [`BreakSource::CtrlC`].
+pub struct BreakHook(());
+
+impl BreakHook {
+    pub fn install(handler: BreakHandler) -> Result<BreakHook, InstallErr> {
+        isr_init()?;
+        if INSTALLED.swap(true, Ordering::Acquire) { return Err(InstallErr::AlreadyInstalled); }
+        HANDLER.store(handler as *mut (), Ordering::Release);
+        PENDING_EXIT.store(NO_PENDING_EXIT, Ordering::Relaxed);
+        lock_data(&HANDLER);
+        lock_data(&PENDING_EXIT);
+        lock_data(&OLD_CTRL_C);
+        lock_data(&OLD_CTRL_BREAK);
+        lock_data(&CTRL_C_STACK);
+        lock_data(&CTRL_BREAK_STACK);
+        OLD_CTRL_C.set(int_21h_ah_35h_get_int(CTRL_C_VECTOR).es_ebx_int_handler);
+        OLD_CTRL_BREAK.set(int_21h_ah_35h_get_int(CTRL_BREAK_VECTOR).es_ebx_int_handler);
+        let cs = code_selector();
+        int_21h_ah_25h_set_int(CTRL_C_VECTOR, PmFarPtr::new(cs, ctrl_c_entry as *const () as usize as u32));
+        int_21h_ah_25h_set_int(CTRL_BREAK_VECTOR, PmFarPtr::new(cs, ctrl_break_entry as *const () as usize as u32));
+        Ok(BreakHook(()))
+    }
+
+    pub fn set_handler(&self, handler: BreakHandler) {
+        HANDLER.store(handler as *mut (), Ordering::Release);
+    }
+}
+
+/// Puts the original INT 23h and INT 1Bh handlers back if the hook is installed.
+pub(crate) fn uninstall() {
+    if INSTALLED.load(Ordering::Acquire) {
+        int_21h_ah_25h_set_int(CTRL_BREAK_VECTOR, OLD_CTRL_BREAK.get());
+        int_21h_ah_25h_set_int(CTRL_C_VECTOR, OLD_CTRL_C.get());
+        HANDLER.store(null_mut(), Ordering::Release);
+        INSTALLED.store(false, Ordering::Release);
+    }
+}
+
+impl Drop for BreakHook {
+    fn drop(&mut self) {
+        uninstall();
+    }
+}
+
+/// Removes every interrupt hook the crate installed and restores the saved video state.
+pub fn cleanup() {
+    uninstall();
+    crate::critical_error::uninstall();
+    crate::mouse::uninstall();
+    crate::keyboard_irq::uninstall();
+    crate::timer_irq::uninstall();
+    crate::speaker::stop_melody();
+    restore_video_state();
+}
+
+/// Runs [`cleanup`] and returns to DOS. Safe to call from an INT 23h handler.
+pub fn terminate(al_exit_code: u8) -> ! {
+    cleanup();
+    int_21h_ah_4Ch_exit(al_exit_code);
+    unreachable!()
+}
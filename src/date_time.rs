@@ -0,0 +1,266 @@
+use crate::*;
+
+/// First year DOS accepts and FAT timestamps can hold.
+pub const DOS_EPOCH_YEAR: u16 = 1980;
+/// Last year INT 21h AH=2Bh accepts.
+pub const DOS_MAX_YEAR: u16 = 2099;
+/// Last year a FAT timestamp can hold.
+pub const FAT_MAX_YEAR: u16 = DOS_EPOCH_YEAR + 0x7F;
+
+const SECONDS_PER_DAY: u64 = 86400;
+/// Days from 1970-01-01 to 1980-01-01.
+const UNIX_DAYS_TO_DOS_EPOCH: u64 = 3652;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct InvalidDateTime;
+
+pub const fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+pub const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct DosDate {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl DosDate {
+    /// Accepts dates from 1980-01-01 to the end of [`FAT_MAX_YEAR`].
+    pub const fn new(year: u16, month: u8, day: u8) -> Result<DosDate, InvalidDateTime> {
+        if year < DOS_EPOCH_YEAR || year > FAT_MAX_YEAR { return Err(InvalidDateTime); }
+        if month < 1 || month > 12 { return Err(InvalidDateTime); }
+        if day < 1 || day > days_in_month(year, month) { return Err(InvalidDateTime); }
+        Ok(DosDate { year, month, day })
+    }
+
+    pub const fn year(self) -> u16 { self.year }
+
+    pub const fn month(self) -> u8 { self.month }
+
+    pub const fn day(self) -> u8 { self.day }
+
+    pub fn today() -> DosDate {
+        let date = int_21h_ah_2Ah_date();
+        DosDate { year: date.cx_year, month: date.dh_month, day: date.dl_day }
+    }
+
+    /// Sets the DOS date, which DOS refuses past [`DOS_MAX_YEAR`].
+    pub fn set(self) -> Result<(), AlErr> {
+        int_21h_ah_2Bh_set_date(self.year, self.month, self.day)
+    }
+
+    /// Days since 1980-01-01.
+    pub fn days_since_epoch(self) -> u32 {
+        let years = (DOS_EPOCH_YEAR .. self.year).map(|y| if is_leap_year(y) { 366 } else { 365 }).sum::<u32>();
+        let months = (1 .. self.month).map(|m| days_in_month(self.year, m) as u32).sum::<u32>();
+        years + months + (self.day - 1) as u32
+    }
+
+    pub fn from_days_since_epoch(mut days: u32) -> Result<DosDate, InvalidDateTime> {
+        let mut year = DOS_EPOCH_YEAR;
+        loop {
+            let year_days = if is_leap_year(year) { 366 } else { 365 };
+            if days < year_days { break; }
+            days -= year_days;
+            year += 1;
+            if year > FAT_MAX_YEAR { return Err(InvalidDateTime); }
+        }
+        let mut month = 1;
+        while days >= days_in_month(year, month) as u32 {
+            days -= days_in_month(year, month) as u32;
+            month += 1;
+        }
+        Ok(DosDate { year, month, day: days as u8 + 1 })
+    }
+
+    /// 0 for Sunday, as DOS reports it.
+    pub fn weekday(self) -> u8 {
+        // 1980-01-01 was a Tuesday.
+        ((self.days_since_epoch() + 2) % 7) as u8
+    }
+
+    /// Decodes bits 15-9 as years since 1980, 8-5 as month and 4-0 as day.
+    pub const fn from_fat(fat_date: u16) -> Result<DosDate, InvalidDateTime> {
+        DosDate::new(DOS_EPOCH_YEAR + (fat_date >> 9), ((fat_date >> 5) & 0x0F) as u8, (fat_date & 0x1F) as u8)
+    }
+
+    pub const fn to_fat(self) -> u16 {
+        ((self.year - DOS_EPOCH_YEAR) << 9) | ((self.month as u16) << 5) | self.day as u16
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct DosTime {
+    hour: u8,
+    minute: u8,
+    second: u8,
+    hundredths: u8,
+}
+
+impl DosTime {
+    pub const MIDNIGHT: DosTime = DosTime { hour: 0, minute: 0, second: 0, hundredths: 0 };
+
+    pub const fn new(hour: u8, minute: u8, second: u8, hundredths: u8) -> Result<DosTime, InvalidDateTime> {
+        if hour > 23 || minute > 59 || second > 59 || hundredths > 99 { return Err(InvalidDateTime); }
+        Ok(DosTime { hour, minute, second, hundredths })
+    }
+
+    pub const fn hour(self) -> u8 { self.hour }
+
+    pub const fn minute(self) -> u8 { self.minute }
+
+    pub const fn second(self) -> u8 { self.second }
+
+    pub const fn hundredths(self) -> u8 { self.hundredths }
+
+    pub fn now() -> DosTime {
+        let time = int_21h_ah_2Ch_time();
+        DosTime { hour: time.ch_hours, minute: time.cl_minutes, second: time.dh_seconds, hundredths: time.dl_hundredths }
+    }
+
+    pub fn set(self) -> Result<(), AlErr> {
+        int_21h_ah_2Dh_set_time(self.hour, self.minute, self.second, self.hundredths)
+    }
+
+    pub const fn seconds_since_midnight(self) -> u32 {
+        self.hour as u32 * 3600 + self.minute as u32 * 60 + self.second as u32
+    }
+
+    /// Decodes bits 15-11 as hours, 10-5 as minutes and 4-0 as seconds divided by two.
+    pub const fn from_fat(fat_time: u16) -> Result<DosTime, InvalidDateTime> {
+        DosTime::new((fat_time >> 11) as u8, ((fat_time >> 5) & 0x3F) as u8, ((fat_time & 0x1F) * 2) as u8, 0)
+    }
+
+    /// Rounds odd seconds down, as FAT keeps two-second steps.
+    pub const fn to_fat(self) -> u16 {
+        ((self.hour as u16) << 11) | ((self.minute as u16) << 5) | (self.second / 2) as u16
+    }
+}
+
+/// Decodes a FAT timestamp as packed in directory entries and find data, date in the high word.
+pub const fn from_fat(fat_date_time: u32) -> Result<(DosDate, DosTime), InvalidDateTime> {
+    let date = match DosDate::from_fat((fat_date_time >> 16) as u16) {
+        Ok(date) => date,
+        Err(e) => return Err(e),
+    };
+    match DosTime::from_fat(fat_date_time as u16) {
+        Ok(time) => Ok((date, time)),
+        Err(e) => Err(e),
+    }
+}
+
+pub const fn to_fat(date: DosDate, time: DosTime) -> u32 {
+    ((date.to_fat() as u32) << 16) | time.to_fat() as u32
+}
+
+/// Seconds since 1970-01-01 00:00, taking the DOS clock, which has no time zone, as UTC.
+pub fn to_unix(date: DosDate, time: DosTime) -> u64 {
+    (UNIX_DAYS_TO_DOS_EPOCH + date.days_since_epoch() as u64) * SECONDS_PER_DAY + time.seconds_since_midnight() as u64
+}
+
+/// The inverse of [`to_unix`], failing outside the years FAT can hold.
+pub fn from_unix(seconds: u64) -> Result<(DosDate, DosTime), InvalidDateTime> {
+    let days = (seconds / SECONDS_PER_DAY).checked_sub(UNIX_DAYS_TO_DOS_EPOCH).ok_or(InvalidDateTime)?;
+    let date = DosDate::from_days_since_epoch(days.try_into().map_err(|_| InvalidDateTime)?)?;
+    let second_of_day = (seconds % SECONDS_PER_DAY) as u32;
+    let time = DosTime {
+        hour: (second_of_day / 3600) as u8,
+        minute: (second_of_day / 60 % 60) as u8,
+        second: (second_of_day % 60) as u8,
+        hundredths: 0,
+    };
+    Ok((date, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: u16, month: u8, day: u8) -> DosDate {
+        DosDate::new(year, month, day).unwrap()
+    }
+
+    fn time(hour: u8, minute: u8, second: u8) -> DosTime {
+        DosTime::new(hour, minute, second, 0).unwrap()
+    }
+
+    #[test]
+    fn fat_date() {
+        assert_eq!(date(2024, 2, 29).to_fat(), 0x585D);
+        assert_eq!(DosDate::from_fat(0x585D), Ok(date(2024, 2, 29)));
+        assert_eq!(DosDate::from_fat(0x0021), Ok(date(1980, 1, 1)));
+        assert_eq!(DosDate::from_fat(0xFF9F), Ok(date(FAT_MAX_YEAR, 12, 31)));
+        assert_eq!(DosDate::from_fat(0x0001), Err(InvalidDateTime));
+        assert_eq!(DosDate::from_fat(0x0020), Err(InvalidDateTime));
+        assert_eq!(DosDate::from_fat(0x005E), Err(InvalidDateTime));
+    }
+
+    #[test]
+    fn fat_time() {
+        assert_eq!(time(13, 45, 30).to_fat(), 0x6DAF);
+        assert_eq!(time(13, 45, 31).to_fat(), 0x6DAF);
+        assert_eq!(DosTime::from_fat(0x6DAF), Ok(time(13, 45, 30)));
+        assert_eq!(DosTime::from_fat(0xC000), Err(InvalidDateTime));
+        assert_eq!(DosTime::from_fat(0x0780), Err(InvalidDateTime));
+        assert_eq!(DosTime::from_fat(0x001E), Err(InvalidDateTime));
+    }
+
+    #[test]
+    fn fat_date_time() {
+        assert_eq!(to_fat(date(2024, 2, 29), time(13, 45, 30)), 0x585D_6DAF);
+        assert_eq!(from_fat(0x585D_6DAF), Ok((date(2024, 2, 29), time(13, 45, 30))));
+        assert_eq!(from_fat(0x585D_C000), Err(InvalidDateTime));
+    }
+
+    #[test]
+    fn days_since_epoch() {
+        assert_eq!(date(1980, 1, 1).days_since_epoch(), 0);
+        assert_eq!(date(1981, 1, 1).days_since_epoch(), 366);
+        assert_eq!(date(2000, 3, 1).days_since_epoch(), 7365);
+        assert_eq!(DosDate::from_days_since_epoch(7365), Ok(date(2000, 3, 1)));
+        assert_eq!(DosDate::from_days_since_epoch(46750), Ok(date(FAT_MAX_YEAR, 12, 31)));
+        assert_eq!(DosDate::from_days_since_epoch(46751), Err(InvalidDateTime));
+        for days in 0 .. 46751 {
+            assert_eq!(DosDate::from_days_since_epoch(days).unwrap().days_since_epoch(), days);
+        }
+    }
+
+    #[test]
+    fn weekday() {
+        assert_eq!(date(1980, 1, 1).weekday(), 2);
+        assert_eq!(date(2000, 1, 1).weekday(), 6);
+        assert_eq!(date(2024, 2, 29).weekday(), 4);
+        assert_eq!(date(FAT_MAX_YEAR, 12, 31).weekday(), 6);
+    }
+
+    #[test]
+    fn unix() {
+        assert_eq!(to_unix(date(1980, 1, 1), DosTime::MIDNIGHT), 315532800);
+        assert_eq!(to_unix(date(2000, 1, 1), DosTime::MIDNIGHT), 946684800);
+        assert_eq!(to_unix(date(2024, 2, 29), time(12, 0, 0)), 1709208000);
+        assert_eq!(from_unix(1709208000), Ok((date(2024, 2, 29), time(12, 0, 0))));
+        assert_eq!(from_unix(315532799), Err(InvalidDateTime));
+        assert_eq!(from_unix(4354819199), Ok((date(FAT_MAX_YEAR, 12, 31), time(23, 59, 59))));
+        assert_eq!(from_unix(4354819200), Err(InvalidDateTime));
+    }
+
+    #[test]
+    fn unix_round_trip() {
+        for (year, month, day) in [(1980, 1, 1), (1980, 2, 29), (2000, 2, 29), (2023, 12, 31), (2096, 2, 29), (2107, 12, 31)] {
+            let date = date(year, month, day);
+            for time in [DosTime::MIDNIGHT, time(0, 0, 1), time(12, 34, 56), time(23, 59, 59)] {
+                assert_eq!(from_unix(to_unix(date, time)), Ok((date, time)));
+            }
+        }
+    }
+}
@@ -0,0 +1,457 @@
+#[cfg(dos)]
+use core::arch::asm;
+use core::mem::MaybeUninit;
+use crate::*;
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn selector_limit(selector: u16) -> Option<u32> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn selector_limit(selector: u16) -> Option<u32> {
+    let mut limit: u32;
+    let mut ok: u8;
+    unsafe {
+        asm!(
+            "lsl {limit:e}, {selector:e}",
+            "setz {ok}",
+            selector = in(reg) selector as u32,
+            limit = lateout(reg) limit,
+            ok = out(reg_byte) ok,
+            options(nomem, nostack),
+        );
+    }
+    if ok != 0 { Some(limit) } else { None }
+}
+
+/// # Safety
+///
+/// `offset` and the bytes after it that are read must lie within `selector`'s limit.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn peek_u8(selector: u16, offset: u32) -> u8 {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// `offset` and the bytes after it that are read must lie within `selector`'s limit.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn peek_u8(selector: u16, offset: u32) -> u8 {
+    let mut value: u8;
+    unsafe {
+        asm!(
+            "push gs",
+            "mov gs, {selector:x}",
+            "mov {value}, byte ptr gs:[{offset:e}]",
+            "pop gs",
+            selector = in(reg) selector,
+            offset = in(reg) offset,
+            value = lateout(reg_byte) value,
+        );
+    }
+    value
+}
+
+/// # Safety
+///
+/// `offset` and the bytes after it that are read must lie within `selector`'s limit.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn peek_u16(selector: u16, offset: u32) -> u16 {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// `offset` and the bytes after it that are read must lie within `selector`'s limit.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn peek_u16(selector: u16, offset: u32) -> u16 {
+    let mut value: u16;
+    unsafe {
+        asm!(
+            "push gs",
+            "mov gs, {selector:x}",
+            "mov {value:x}, word ptr gs:[{offset:e}]",
+            "pop gs",
+            selector = in(reg) selector,
+            offset = in(reg) offset,
+            value = lateout(reg) value,
+        );
+    }
+    value
+}
+
+/// # Safety
+///
+/// `offset` and the bytes after it that are read must lie within `selector`'s limit.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn peek_u32(selector: u16, offset: u32) -> u32 {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// `offset` and the bytes after it that are read must lie within `selector`'s limit.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn peek_u32(selector: u16, offset: u32) -> u32 {
+    let mut value: u32;
+    unsafe {
+        asm!(
+            "push gs",
+            "mov gs, {selector:x}",
+            "mov {value:e}, dword ptr gs:[{offset:e}]",
+            "pop gs",
+            selector = in(reg) selector,
+            offset = in(reg) offset,
+            value = lateout(reg) value,
+        );
+    }
+    value
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn poke_u8(selector: u16, offset: u32, value: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn poke_u8(selector: u16, offset: u32, value: u8) {
+    unsafe {
+        asm!(
+            "push gs",
+            "mov gs, {selector:x}",
+            "mov byte ptr gs:[{offset:e}], {value}",
+            "pop gs",
+            selector = in(reg) selector,
+            offset = in(reg) offset,
+            value = in(reg_byte) value,
+        );
+    }
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn poke_u16(selector: u16, offset: u32, value: u16) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn poke_u16(selector: u16, offset: u32, value: u16) {
+    unsafe {
+        asm!(
+            "push gs",
+            "mov gs, {selector:x}",
+            "mov word ptr gs:[{offset:e}], {value:x}",
+            "pop gs",
+            selector = in(reg) selector,
+            offset = in(reg) offset,
+            value = in(reg) value,
+        );
+    }
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn poke_u32(selector: u16, offset: u32, value: u32) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn poke_u32(selector: u16, offset: u32, value: u32) {
+    unsafe {
+        asm!(
+            "push gs",
+            "mov gs, {selector:x}",
+            "mov dword ptr gs:[{offset:e}], {value:e}",
+            "pop gs",
+            selector = in(reg) selector,
+            offset = in(reg) offset,
+            value = in(reg) value,
+        );
+    }
+}
+
+/// # Safety
+///
+/// `dest.len()` bytes from `offset` must lie within `selector`'s limit.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn copy_from_far(selector: u16, offset: u32, dest: &mut [MaybeUninit<u8>]) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// `dest.len()` bytes from `offset` must lie within `selector`'s limit.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn copy_from_far(selector: u16, offset: u32, dest: &mut [MaybeUninit<u8>]) {
+    unsafe {
+        asm!(
+            "push esi",
+            "push ds",
+            "mov esi, {offset:e}",
+            "mov ds, {selector:x}",
+            "rep movsb",
+            "pop ds",
+            "pop esi",
+            selector = in(reg) selector,
+            offset = in(reg) offset,
+            inlateout("ecx") dest.len() => _,
+            inlateout("edi") dest.as_mut_ptr() => _,
+        );
+    }
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn copy_to_far(selector: u16, offset: u32, src: &[u8]) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn copy_to_far(selector: u16, offset: u32, src: &[u8]) {
+    unsafe {
+        asm!(
+            "push esi",
+            "push es",
+            "mov esi, {src:e}",
+            "mov es, {selector:x}",
+            "rep movsb",
+            "pop es",
+            "pop esi",
+            selector = in(reg) selector,
+            src = in(reg) src.as_ptr(),
+            inlateout("ecx") src.len() => _,
+            inlateout("edi") offset => _,
+        );
+    }
+}
+
+/// # Safety
+///
+/// Both ranges must lie within their selectors' limits, and the destination must not alias
+/// memory Rust code holds references to.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn movedata(src_selector: u16, src_offset: u32, dest_selector: u16, dest_offset: u32, len: u32) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// Both ranges must lie within their selectors' limits, and the destination must not alias
+/// memory Rust code holds references to.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn movedata(src_selector: u16, src_offset: u32, dest_selector: u16, dest_offset: u32, len: u32) {
+    unsafe {
+        asm!(
+            "push esi",
+            "push ds",
+            "push es",
+            "mov esi, {src_offset:e}",
+            "mov es, {dest_selector:x}",
+            "mov ds, {src_selector:x}",
+            "rep movsb",
+            "pop es",
+            "pop ds",
+            "pop esi",
+            src_selector = in(reg) src_selector,
+            dest_selector = in(reg) dest_selector,
+            src_offset = in(reg) src_offset,
+            inlateout("ecx") len => _,
+            inlateout("edi") dest_offset => _,
+        );
+    }
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn fill_far(selector: u16, offset: u32, len: u32, value: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// The bytes written must lie within `selector`'s limit and must not alias memory Rust code
+/// holds references to, such as objects reachable through the flat data selector.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn fill_far(selector: u16, offset: u32, len: u32, value: u8) {
+    unsafe {
+        asm!(
+            "push es",
+            "mov es, {selector:x}",
+            "rep stosb",
+            "pop es",
+            selector = in(reg) selector,
+            in("al") value,
+            inlateout("ecx") len => _,
+            inlateout("edi") offset => _,
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct FarSlice {
+    selector: u16,
+    offset: u32,
+    len: u32,
+}
+
+impl FarSlice {
+    /// # Safety
+    ///
+    /// The memory `selector` spans must not alias memory Rust code holds references to,
+    /// as the flat data and code selectors do.
+    pub unsafe fn new(selector: u16) -> Option<FarSlice> {
+        let limit = selector_limit(selector)?;
+        Some(FarSlice { selector, offset: 0, len: limit.saturating_add(1) })
+    }
+
+    /// # Safety
+    ///
+    /// The `len` bytes at `offset` in `selector` must not alias memory Rust code holds
+    /// references to.
+    pub unsafe fn from_parts(selector: u16, offset: u32, len: u32) -> Option<FarSlice> {
+        unsafe { FarSlice::new(selector) }?.slice(offset, len)
+    }
+
+    pub fn rm_segment(segment: u16) -> Result<FarSlice, AxErr> {
+        let selector = int_31h_ax_0002h_segment_to_descriptor(segment)?.ax_selector;
+        Ok(FarSlice { selector, offset: 0, len: 0x10000 })
+    }
+
+    /// # Safety
+    ///
+    /// `alloc` must be a live DOS memory block, as [`int_31h_ax_0100h_rm_alloc`] returns.
+    pub unsafe fn rm_alloc(alloc: &RmAlloc) -> Option<FarSlice> {
+        unsafe { FarSlice::new(alloc.dx_selector) }
+    }
+
+    pub fn selector(&self) -> u16 { self.selector }
+
+    pub fn offset(&self) -> u32 { self.offset }
+
+    pub fn len(&self) -> u32 { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    pub fn ptr(&self) -> PmFarPtr {
+        PmFarPtr { offset: self.offset, selector: self.selector }
+    }
+
+    pub fn slice(&self, offset: u32, len: u32) -> Option<FarSlice> {
+        if offset.checked_add(len)? > self.len { return None; }
+        Some(FarSlice { selector: self.selector, offset: self.offset + offset, len })
+    }
+
+    fn check(&self, offset: u32, size: u32) -> u32 {
+        assert!(
+            offset.checked_add(size).is_some_and(|end| end <= self.len),
+            "far slice index out of bounds"
+        );
+        self.offset + offset
+    }
+
+    pub fn read_u8(&self, offset: u32) -> u8 {
+        unsafe { peek_u8(self.selector, self.check(offset, 1)) }
+    }
+
+    pub fn read_u16(&self, offset: u32) -> u16 {
+        unsafe { peek_u16(self.selector, self.check(offset, 2)) }
+    }
+
+    pub fn read_u32(&self, offset: u32) -> u32 {
+        unsafe { peek_u32(self.selector, self.check(offset, 4)) }
+    }
+
+    pub fn write_u8(&self, offset: u32, value: u8) {
+        unsafe { poke_u8(self.selector, self.check(offset, 1), value); }
+    }
+
+    pub fn write_u16(&self, offset: u32, value: u16) {
+        unsafe { poke_u16(self.selector, self.check(offset, 2), value); }
+    }
+
+    pub fn write_u32(&self, offset: u32, value: u32) {
+        unsafe { poke_u32(self.selector, self.check(offset, 4), value); }
+    }
+
+    pub fn read(&self, offset: u32, dest: &mut [u8]) {
+        let dest = unsafe { &mut *(dest as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.read_uninit(offset, dest);
+    }
+
+    pub fn read_uninit(&self, offset: u32, dest: &mut [MaybeUninit<u8>]) {
+        let len = u32::try_from(dest.len()).unwrap();
+        unsafe { copy_from_far(self.selector, self.check(offset, len), dest); }
+    }
+
+    pub fn write(&self, offset: u32, src: &[u8]) {
+        let len = u32::try_from(src.len()).unwrap();
+        unsafe { copy_to_far(self.selector, self.check(offset, len), src); }
+    }
+
+    pub fn fill(&self, value: u8) {
+        unsafe { fill_far(self.selector, self.offset, self.len, value); }
+    }
+
+    pub fn fill_at(&self, offset: u32, len: u32, value: u8) {
+        unsafe { fill_far(self.selector, self.check(offset, len), len, value); }
+    }
+
+    pub fn copy_from_far_slice(&self, offset: u32, src: &FarSlice) {
+        unsafe { movedata(src.selector, src.offset, self.selector, self.check(offset, src.len), src.len); }
+    }
+}
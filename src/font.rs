@@ -0,0 +1,112 @@
+use core::cmp::min;
+use crate::*;
+use crate::xfer::XferBuf;
+
+pub const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+pub const PSF1_MODE_512: u8 = 0x01;
+pub const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PsfErr {
+    BadMagic,
+    Truncated,
+    UnsupportedWidth(u32),
+    UnsupportedHeight(u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Psf<'a> {
+    glyphs: &'a [u8],
+    count: u16,
+    height: u8,
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, PsfErr> {
+    let bytes = bytes.get(offset .. offset + 4).ok_or(PsfErr::Truncated)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+impl<'a> Psf<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Psf<'a>, PsfErr> {
+        if bytes.starts_with(&PSF1_MAGIC) {
+            let header = bytes.get(.. 4).ok_or(PsfErr::Truncated)?;
+            let count = if header[2] & PSF1_MODE_512 != 0 { 512 } else { 256 };
+            let height = header[3];
+            if height == 0 || height > 32 { return Err(PsfErr::UnsupportedHeight(height as u32)); }
+            let glyphs = bytes.get(4 .. 4 + count as usize * height as usize).ok_or(PsfErr::Truncated)?;
+            Ok(Psf { glyphs, count, height })
+        } else if bytes.starts_with(&PSF2_MAGIC) {
+            let header_size = u32_at(bytes, 8)? as usize;
+            let count = u32_at(bytes, 16)?;
+            let bytes_per_glyph = u32_at(bytes, 20)?;
+            let height = u32_at(bytes, 24)?;
+            let width = u32_at(bytes, 28)?;
+            if width == 0 || width > 8 { return Err(PsfErr::UnsupportedWidth(width)); }
+            if height == 0 || height > 32 || bytes_per_glyph != height {
+                return Err(PsfErr::UnsupportedHeight(height));
+            }
+            let count = min(count, 512) as u16;
+            let end = (count as usize).checked_mul(height as usize)
+                .and_then(|len| header_size.checked_add(len))
+                .ok_or(PsfErr::Truncated)?;
+            let glyphs = bytes.get(header_size .. end).ok_or(PsfErr::Truncated)?;
+            Ok(Psf { glyphs, count, height: height as u8 })
+        } else {
+            Err(PsfErr::BadMagic)
+        }
+    }
+
+    pub fn glyphs(&self) -> &'a [u8] { self.glyphs }
+
+    pub fn count(&self) -> u16 { self.count }
+
+    pub fn height(&self) -> u8 { self.height }
+
+    pub fn glyph(&self, index: u16) -> &'a [u8] {
+        let start = index as usize * self.height as usize;
+        &self.glyphs[start .. start + self.height as usize]
+    }
+}
+
+pub fn load_font(
+    xfer: &XferBuf,
+    glyphs: &[u8],
+    height: u8,
+    first: u8,
+    block: u8,
+    program: bool
+) -> Result<(), DpmiErr> {
+    assert!(height != 0 && glyphs.len().is_multiple_of(height as usize));
+    let count = glyphs.len() / height as usize;
+    assert!(first as usize + count <= 256);
+    let chunk = min(xfer.len() as usize, 0xFFFF) / height as usize;
+    assert!(chunk != 0, "transfer buffer is too small");
+    let mut loaded = 0;
+    while loaded < count {
+        let n = min(count - loaded, chunk);
+        xfer.copy_in(0, &glyphs[loaded * height as usize .. (loaded + n) * height as usize]);
+        let dx_first = (first as usize + loaded) as u16;
+        if program {
+            int_10h_ax_1110h_load_font_and_program(xfer.ptr(), n as u16, dx_first, block, height)?;
+        } else {
+            int_10h_ax_1100h_load_font(xfer.ptr(), n as u16, dx_first, block, height)?;
+        }
+        loaded += n;
+    }
+    Ok(())
+}
+
+pub fn load_psf(xfer: &XferBuf, psf: &Psf, first: u8, block: u8, program: bool) -> Result<(), DpmiErr> {
+    let count = min(psf.count() as usize, 256 - first as usize);
+    load_font(xfer, &psf.glyphs()[.. count * psf.height() as usize], psf.height(), first, block, program)
+}
+
+pub fn set_43_50_line_mode() {
+    let _ = int_10h_ah_00h_set_video_mode(0x03);
+    int_10h_ax_1112h_load_rom_8x8_and_program(0);
+}
+
+pub fn set_25_line_mode() {
+    let _ = int_10h_ah_00h_set_video_mode(0x03);
+    int_10h_ax_1114h_load_rom_8x16_and_program(0);
+}
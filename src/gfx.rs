@@ -0,0 +1,314 @@
+use core::cmp::min;
+use crate::*;
+use crate::far_mem::FarSlice;
+use crate::vga::*;
+
+pub const MODE_13H: u8 = 0x13;
+pub const VGA_SEGMENT: u16 = 0xA000;
+
+pub const MODE_13H_WIDTH: u16 = 320;
+pub const MODE_13H_HEIGHT: u16 = 200;
+
+pub const MODE_X_WIDTH: u16 = 320;
+pub const MODE_X_HEIGHT: u16 = 240;
+pub const MODE_X_STRIDE: u16 = MODE_X_WIDTH / 4;
+pub const MODE_X_PAGE_SIZE: u16 = MODE_X_STRIDE * MODE_X_HEIGHT;
+pub const MODE_X_PAGES: u8 = 3;
+
+#[derive(Debug, Clone)]
+pub enum GfxErr {
+    ModeNotSet(u8),
+    Selector(AxErr),
+}
+
+impl From<AxErr> for GfxErr {
+    fn from(e: AxErr) -> GfxErr { GfxErr::Selector(e) }
+}
+
+fn set_mode(al_mode: u8) -> Result<(), GfxErr> {
+    // AL on return is a BIOS-specific mode flag, so check the resulting mode instead.
+    let _ = int_10h_ah_00h_set_video_mode(al_mode);
+    let mode = int_10h_ah_0Fh_video_mode().al_mode;
+    if mode & 0x7F == al_mode { Ok(()) } else { Err(GfxErr::ModeNotSet(mode)) }
+}
+
+fn clip(x: i32, len: i32, limit: u16) -> Option<(u16, u16)> {
+    let start = x.max(0);
+    let end = x.saturating_add(len).min(limit as i32);
+    if start >= end { return None; }
+    Some((start as u16, (end - start) as u16))
+}
+
+pub trait Surface {
+    fn width(&self) -> u16;
+
+    fn height(&self) -> u16;
+
+    fn put_pixel(&mut self, x: u16, y: u16, color: u8);
+
+    fn get_pixel(&self, x: u16, y: u16) -> u8;
+
+    fn fill_span(&mut self, x: u16, y: u16, len: u16, color: u8) {
+        for i in 0 .. len {
+            self.put_pixel(x + i, y, color);
+        }
+    }
+
+    fn blit_span(&mut self, x: u16, y: u16, pixels: &[u8]) {
+        for (i, &color) in pixels.iter().enumerate() {
+            self.put_pixel(x + i as u16, y, color);
+        }
+    }
+
+    fn pixel(&mut self, x: i32, y: i32, color: u8) {
+        if (0 .. self.width() as i32).contains(&x) && (0 .. self.height() as i32).contains(&y) {
+            self.put_pixel(x as u16, y as u16, color);
+        }
+    }
+
+    fn hline(&mut self, x: i32, y: i32, len: i32, color: u8) {
+        self.fill_rect(x, y, len, 1, color);
+    }
+
+    fn vline(&mut self, x: i32, y: i32, len: i32, color: u8) {
+        self.fill_rect(x, y, 1, len, color);
+    }
+
+    fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: u8) {
+        let Some((x, width)) = clip(x, width, self.width()) else { return; };
+        let Some((y, height)) = clip(y, height, self.height()) else { return; };
+        for row in y .. y + height {
+            self.fill_span(x, row, width, color);
+        }
+    }
+
+    fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: u8) {
+        if width <= 0 || height <= 0 { return; }
+        self.hline(x, y, width, color);
+        self.hline(x, y + height - 1, width, color);
+        self.vline(x, y, height, color);
+        self.vline(x + width - 1, y, height, color);
+    }
+
+    fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: u8) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+        loop {
+            self.pixel(x, y, color);
+            if x == x1 && y == y1 { break; }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn blit(&mut self, x: i32, y: i32, width: u16, height: u16, pixels: &[u8]) {
+        assert!(pixels.len() >= width as usize * height as usize);
+        let Some((dest_x, span)) = clip(x, width as i32, self.width()) else { return; };
+        let Some((dest_y, rows)) = clip(y, height as i32, self.height()) else { return; };
+        let src_x = (dest_x as i32 - x) as usize;
+        let src_y = (dest_y as i32 - y) as usize;
+        for row in 0 .. rows as usize {
+            let start = (src_y + row) * width as usize + src_x;
+            self.blit_span(dest_x, dest_y + row as u16, &pixels[start .. start + span as usize]);
+        }
+    }
+
+    fn blit_masked(&mut self, x: i32, y: i32, width: u16, height: u16, pixels: &[u8], transparent: u8) {
+        assert!(pixels.len() >= width as usize * height as usize);
+        for row in 0 .. height as i32 {
+            for col in 0 .. width as i32 {
+                let color = pixels[row as usize * width as usize + col as usize];
+                if color != transparent {
+                    self.pixel(x + col, y + row, color);
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self, color: u8) {
+        self.fill_rect(0, 0, self.width() as i32, self.height() as i32, color);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mode13h {
+    mem: FarSlice,
+}
+
+impl Mode13h {
+    pub fn new() -> Result<Mode13h, GfxErr> {
+        set_mode(MODE_13H)?;
+        Ok(Mode13h { mem: FarSlice::rm_segment(VGA_SEGMENT)? })
+    }
+
+    pub fn mem(&self) -> &FarSlice { &self.mem }
+
+    pub fn wait_vsync(&self) {
+        wait_vretrace();
+    }
+
+    pub fn present(&mut self, frame: &[u8]) {
+        assert!(frame.len() >= MODE_13H_WIDTH as usize * MODE_13H_HEIGHT as usize);
+        wait_vretrace();
+        self.mem.write(0, &frame[.. MODE_13H_WIDTH as usize * MODE_13H_HEIGHT as usize]);
+    }
+}
+
+impl Surface for Mode13h {
+    fn width(&self) -> u16 { MODE_13H_WIDTH }
+
+    fn height(&self) -> u16 { MODE_13H_HEIGHT }
+
+    fn put_pixel(&mut self, x: u16, y: u16, color: u8) {
+        assert!(x < MODE_13H_WIDTH && y < MODE_13H_HEIGHT);
+        self.mem.write_u8(y as u32 * MODE_13H_WIDTH as u32 + x as u32, color);
+    }
+
+    fn get_pixel(&self, x: u16, y: u16) -> u8 {
+        assert!(x < MODE_13H_WIDTH && y < MODE_13H_HEIGHT);
+        self.mem.read_u8(y as u32 * MODE_13H_WIDTH as u32 + x as u32)
+    }
+
+    fn fill_span(&mut self, x: u16, y: u16, len: u16, color: u8) {
+        assert!(x as u32 + len as u32 <= MODE_13H_WIDTH as u32 && y < MODE_13H_HEIGHT);
+        let offset = y as u32 * MODE_13H_WIDTH as u32 + x as u32;
+        self.mem.fill_at(offset, len as u32, color);
+    }
+
+    fn blit_span(&mut self, x: u16, y: u16, pixels: &[u8]) {
+        assert!(x as usize + pixels.len() <= MODE_13H_WIDTH as usize && y < MODE_13H_HEIGHT);
+        self.mem.write(y as u32 * MODE_13H_WIDTH as u32 + x as u32, pixels);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModeX {
+    mem: FarSlice,
+    draw_page: u8,
+    visible_page: u8,
+}
+
+impl ModeX {
+    pub fn new() -> Result<ModeX, GfxErr> {
+        set_mode(MODE_13H)?;
+        let mem = FarSlice::rm_segment(VGA_SEGMENT)?;
+        seq_write(SEQ_MEMORY_MODE, 0x06);
+        seq_write(SEQ_RESET, 0x01);
+        unsafe { port_out_u8(VGA_MISC_OUTPUT_WRITE, 0xE3); }
+        seq_write(SEQ_RESET, 0x03);
+        crtc_write(CRTC_VERTICAL_RETRACE_END, crtc_read(CRTC_VERTICAL_RETRACE_END) & 0x7F);
+        for &(index, value) in &[
+            (0x06, 0x0D), (0x07, 0x3E), (0x09, 0x41), (0x10, 0xEA), (0x11, 0xAC),
+            (0x12, 0xDF), (CRTC_UNDERLINE_LOCATION, 0x00), (0x15, 0xE7), (0x16, 0x06),
+            (CRTC_MODE_CONTROL, 0xE3),
+        ] {
+            crtc_write(index, value);
+        }
+        seq_write(SEQ_MAP_MASK, 0x0F);
+        mem.fill(0);
+        Ok(ModeX { mem, draw_page: 1, visible_page: 0 })
+    }
+
+    pub fn mem(&self) -> &FarSlice { &self.mem }
+
+    pub fn draw_page(&self) -> u8 { self.draw_page }
+
+    pub fn visible_page(&self) -> u8 { self.visible_page }
+
+    pub fn set_draw_page(&mut self, page: u8) {
+        assert!(page < MODE_X_PAGES);
+        self.draw_page = page;
+    }
+
+    fn page_offset(page: u8) -> u32 {
+        page as u32 * MODE_X_PAGE_SIZE as u32
+    }
+
+    fn offset(&self, x: u16, y: u16) -> u32 {
+        assert!(x < MODE_X_WIDTH && y < MODE_X_HEIGHT);
+        ModeX::page_offset(self.draw_page) + y as u32 * MODE_X_STRIDE as u32 + (x / 4) as u32
+    }
+
+    pub fn show_page(&mut self, page: u8) {
+        assert!(page < MODE_X_PAGES);
+        wait_display_enable();
+        set_start_address(ModeX::page_offset(page) as u16);
+        wait_vretrace();
+        self.visible_page = page;
+    }
+
+    pub fn flip(&mut self) {
+        let page = self.draw_page;
+        self.draw_page = self.visible_page;
+        self.show_page(page);
+    }
+
+    pub fn set_display_start(&mut self, page: u8, x: u16, y: u16) {
+        assert!(page < MODE_X_PAGES);
+        let offset = ModeX::page_offset(page) + y as u32 * MODE_X_STRIDE as u32 + (x / 4) as u32;
+        wait_display_enable();
+        set_start_address(offset as u16);
+        wait_vretrace();
+        set_pel_panning(((x & 3) * 2) as u8);
+        self.visible_page = page;
+    }
+}
+
+impl Surface for ModeX {
+    fn width(&self) -> u16 { MODE_X_WIDTH }
+
+    fn height(&self) -> u16 { MODE_X_HEIGHT }
+
+    fn put_pixel(&mut self, x: u16, y: u16, color: u8) {
+        let offset = self.offset(x, y);
+        seq_write(SEQ_MAP_MASK, 1 << (x & 3));
+        self.mem.write_u8(offset, color);
+    }
+
+    fn get_pixel(&self, x: u16, y: u16) -> u8 {
+        let offset = self.offset(x, y);
+        gc_write(GC_READ_MAP_SELECT, (x & 3) as u8);
+        self.mem.read_u8(offset)
+    }
+
+    fn fill_span(&mut self, x: u16, y: u16, len: u16, color: u8) {
+        assert!(x as u32 + len as u32 <= MODE_X_WIDTH as u32);
+        for plane in 0 .. min(len, 4) {
+            let first = x + plane;
+            let count = (len - plane).div_ceil(4);
+            seq_write(SEQ_MAP_MASK, 1 << (first & 3));
+            self.mem.fill_at(self.offset(first, y), count as u32, color);
+        }
+    }
+
+    fn blit_span(&mut self, x: u16, y: u16, pixels: &[u8]) {
+        assert!(x as usize + pixels.len() <= MODE_X_WIDTH as usize);
+        let mut buf = [0u8; MODE_X_STRIDE as usize];
+        for plane in 0 .. min(pixels.len(), 4) {
+            let first = x + plane as u16;
+            let mut count = 0;
+            for &color in pixels[plane ..].iter().step_by(4) {
+                buf[count] = color;
+                count += 1;
+            }
+            seq_write(SEQ_MAP_MASK, 1 << (first & 3));
+            self.mem.write(self.offset(first, y), &buf[.. count]);
+        }
+    }
+
+    fn clear(&mut self, color: u8) {
+        seq_write(SEQ_MAP_MASK, 0x0F);
+        self.mem.fill_at(ModeX::page_offset(self.draw_page), MODE_X_PAGE_SIZE as u32, color);
+    }
+}
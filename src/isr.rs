@@ -0,0 +1,226 @@
+#[cfg(dos)]
+use core::arch::asm;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering};
+use crate::*;
+
+pub(crate) const ISR_STACK_SIZE: usize = 4096;
+
+pub(crate) const PIC1_COMMAND: u16 = 0x20;
+pub(crate) const PIC_EOI: u8 = 0x20;
+
+/// Data selector loaded into DS, ES and SS before a handler body runs.
+pub(crate) static ISR_DS: AtomicU16 = AtomicU16::new(0);
+
+static CODE_LOCKED: AtomicBool = AtomicBool::new(false);
+
+#[repr(C, align(16))]
+pub(crate) struct IsrStack(UnsafeCell<[u8; ISR_STACK_SIZE]>);
+
+// Only the interrupt handler owning the stack touches it.
+unsafe impl Sync for IsrStack { }
+
+impl IsrStack {
+    pub(crate) const fn new() -> IsrStack { IsrStack(UnsafeCell::new([0; ISR_STACK_SIZE])) }
+}
+
+/// The previous handler as an fword, jumped to when a handler body returns nonzero.
+#[repr(C)]
+pub(crate) struct IsrChain {
+    offset: AtomicU32,
+    selector: AtomicU16,
+}
+
+impl IsrChain {
+    pub(crate) const fn new() -> IsrChain {
+        IsrChain { offset: AtomicU32::new(0), selector: AtomicU16::new(0) }
+    }
+
+    pub(crate) fn set(&self, handler: PmFarPtr) {
+        self.offset.store(handler.offset, Ordering::Relaxed);
+        self.selector.store(handler.selector, Ordering::Release);
+    }
+
+    pub(crate) fn get(&self) -> PmFarPtr {
+        PmFarPtr { selector: self.selector.load(Ordering::Acquire), offset: self.offset.load(Ordering::Relaxed) }
+    }
+}
+
+#[cfg(not(dos))]
+pub(crate) fn code_selector() -> u16 {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+pub(crate) fn code_selector() -> u16 {
+    let mut cs: u32;
+    unsafe {
+        asm!("mov {cs:e}, cs", cs = lateout(reg) cs, options(nomem, nostack, preserves_flags));
+    }
+    cs as u16
+}
+
+#[cfg(not(dos))]
+pub(crate) fn data_selector() -> u16 {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+pub(crate) fn data_selector() -> u16 {
+    let mut ds: u32;
+    unsafe {
+        asm!("mov {ds:e}, ds", ds = lateout(reg) ds, options(nomem, nostack, preserves_flags));
+    }
+    ds as u16
+}
+
+#[cfg(dos)]
+#[allow(non_upper_case_globals)]
+extern "C" {
+    /// The image's DOS header, which the linker places at the start of the image.
+    static __ImageBase: u8;
+}
+
+/// The image's code as an offset and a length in the code segment, from `BaseOfCode` and
+/// `SizeOfCode` in the PE optional header.
+#[cfg(not(dos))]
+fn code_range() -> (u32, u32) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// The image's code as an offset and a length in the code segment, from `BaseOfCode` and
+/// `SizeOfCode` in the PE optional header.
+#[cfg(dos)]
+fn code_range() -> (u32, u32) {
+    let image = &raw const __ImageBase;
+    let u32_at = |offset: u32| unsafe { image.add(offset as usize).cast::<u32>().read_unaligned() };
+    let optional_header = u32_at(0x3C) + 24;
+    (image as usize as u32 + u32_at(optional_header + 20), u32_at(optional_header + 4))
+}
+
+/// Locks the pages under `len` bytes at `offset` in `selector`.
+fn lock(selector: u16, offset: u32, len: u32) -> Result<(), AxErr> {
+    let base = int_31h_ax_0006h_segment_addr(selector)?.cx_dx_base;
+    int_31h_ax_0600h_lock(base.wrapping_add(offset), len)
+}
+
+/// Locks `data`, ignoring hosts that refuse; they refuse the code first, in [`isr_init`].
+pub(crate) fn lock_data<T>(data: &'static T) {
+    let _ = lock(data_selector(), data as *const T as usize as u32, size_of::<T>() as u32);
+}
+
+pub(crate) fn lock_slice<T>(data: &'static [T]) {
+    let _ = lock(data_selector(), data.as_ptr() as usize as u32, size_of_val(data) as u32);
+}
+
+#[derive(Debug, Clone)]
+pub enum InstallErr {
+    AlreadyInstalled,
+    /// The host could not lock the code handlers run.
+    Lock(AxErr),
+}
+
+/// Prepares for installing handlers: records the data selector, locks the shared state and,
+/// until that succeeds once, the image's code, since any code a handler calls must stay paged in.
+pub(crate) fn isr_init() -> Result<(), InstallErr> {
+    if !CODE_LOCKED.load(Ordering::Relaxed) {
+        let (offset, len) = code_range();
+        lock(code_selector(), offset, len).map_err(InstallErr::Lock)?;
+        CODE_LOCKED.store(true, Ordering::Relaxed);
+    }
+    ISR_DS.store(data_selector(), Ordering::Release);
+    lock_data(&ISR_DS);
+    Ok(())
+}
+
+/// Maps an IRQ to the protected-mode interrupt vector the host delivers it on.
+pub fn irq_vector(irq: u8) -> u8 {
+    let version = int_31h_ax_0400h_version();
+    if irq < 8 {
+        version.dh_master_pic_base + irq
+    } else {
+        version.dl_slave_pic_base + (irq - 8)
+    }
+}
+
+pub(crate) fn pic_eoi() {
+    unsafe { port_out_u8(PIC1_COMMAND, PIC_EOI); }
+}
+
+#[cfg(dos)]
+const FLAGS_IF: u32 = 0x0200;
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub(crate) fn without_interrupts<R>(f: impl FnOnce() -> R) -> R {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// Runs `f` with interrupts disabled, restoring the interrupt flag afterwards.
+#[cfg(dos)]
+pub(crate) fn without_interrupts<R>(f: impl FnOnce() -> R) -> R {
+    let flags: u32;
+    unsafe {
+        asm!("pushfd", "pop {flags}", "cli", flags = lateout(reg) flags);
+    }
+    let result = f();
+    if flags & FLAGS_IF != 0 {
+        unsafe {
+            asm!("sti");
+        }
+    }
+    result
+}
+
+/// Defines a naked interrupt entry point `$name` that switches to `$stack` with our data
+/// selectors and calls `$handler: extern "C" fn() -> u32`. A zero result returns with
+/// `iretd`, anything else jumps on to the handler stored in `$chain`.
+macro_rules! isr_entry {
+    ($name:ident, $handler:path, $stack:path, $chain:path) => {
+        #[cfg(not(dos))]
+        unsafe extern "C" fn $name() {
+            // Only the dos entry calls the handler; naming it keeps it from looking unused.
+            let _ = $handler;
+            panic!("cfg(target_os=\"dos\")");
+        }
+
+        #[cfg(dos)]
+        #[unsafe(naked)]
+        unsafe extern "C" fn $name() {
+            core::arch::naked_asm!(
+                "pushad",
+                "push ds",
+                "push es",
+                "mov ds, word ptr cs:[{ds}]",
+                "mov es, word ptr cs:[{ds}]",
+                "mov ecx, esp",
+                "mov edx, ss",
+                "mov ss, word ptr cs:[{ds}]",
+                "lea esp, [{stack} + {stack_size}]",
+                "push edx",
+                "push ecx",
+                "cld",
+                "call {handler}",
+                "pop ecx",
+                "pop edx",
+                "mov ss, dx",
+                "mov esp, ecx",
+                "test eax, eax",
+                "pop es",
+                "pop ds",
+                "popad",
+                "jnz 2f",
+                "iretd",
+                "2:",
+                "jmp fword ptr cs:[{chain}]",
+                ds = sym $crate::isr::ISR_DS,
+                stack = sym $stack,
+                stack_size = const $crate::isr::ISR_STACK_SIZE,
+                handler = sym $handler,
+                chain = sym $chain,
+            );
+        }
+    };
+}
+
+pub(crate) use isr_entry;
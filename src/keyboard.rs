@@ -0,0 +1,213 @@
+use crate::*;
+use crate::far_mem::FarSlice;
+use crate::text::BDA_SEGMENT;
+
+pub const BDA_KEYBOARD_STATUS_3: u32 = 0x96;
+
+const ENHANCED_KEYBOARD: u8 = 0x10;
+
+/// Letters produced by Alt combinations, indexed by scan code from 10h.
+const ALT_LETTERS: &[u8; 0x23] = b"QWERTYUIOP\0\0\0\0ASDFGHJKL\0\0\0\0\0ZXCVBNM";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers { shift: false, ctrl: false, alt: false };
+    pub const SHIFT: Modifiers = Modifiers { shift: true, ctrl: false, alt: false };
+    pub const CTRL: Modifiers = Modifiers { shift: false, ctrl: true, alt: false };
+    pub const ALT: Modifiers = Modifiers { shift: false, ctrl: false, alt: true };
+
+    pub const fn from_shift_flags(flags: u16) -> Modifiers {
+        Modifiers {
+            shift: flags & (SHIFT_LEFT_SHIFT | SHIFT_RIGHT_SHIFT) != 0,
+            ctrl: flags & SHIFT_CTRL != 0,
+            alt: flags & SHIFT_ALT != 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Key {
+    Char(u8),
+    Escape,
+    Enter,
+    Backspace,
+    Tab,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    Center,
+    F(u8),
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct KeyEvent {
+    pub scan_code: u8,
+    pub ascii: u8,
+}
+
+impl From<AxKey> for KeyEvent {
+    fn from(key: AxKey) -> KeyEvent { KeyEvent { scan_code: key.ah_scan_code, ascii: key.al_ascii } }
+}
+
+impl KeyEvent {
+    pub const fn new(scan_code: u8, ascii: u8) -> KeyEvent {
+        KeyEvent { scan_code, ascii }
+    }
+
+    /// `true` for keys with no ASCII code: arrows, function keys and Alt combinations.
+    ///
+    /// Enhanced services report the gray cursor keys with ASCII E0h.
+    pub const fn is_extended(self) -> bool {
+        self.ascii == 0 || (self.ascii == 0xE0 && self.scan_code != 0)
+    }
+
+    pub fn key(self) -> Key {
+        self.decode().0
+    }
+
+    /// Modifiers implied by the key code itself. The BIOS encodes Shift, Ctrl and Alt
+    /// into the scan code only for some keys; use [`crate::keyboard::modifiers`] for the live state.
+    pub fn modifiers(self) -> Modifiers {
+        self.decode().1
+    }
+
+    pub fn decode(self) -> (Key, Modifiers) {
+        if !self.is_extended() {
+            return match (self.scan_code, self.ascii) {
+                (_, 0x1B) => (Key::Escape, Modifiers::NONE),
+                (0x1C | 0xE0, 0x0D) => (Key::Enter, Modifiers::NONE),
+                (0x1C | 0xE0, 0x0A) => (Key::Enter, Modifiers::CTRL),
+                (0x0E, 0x08) => (Key::Backspace, Modifiers::NONE),
+                (0x0E, 0x7F) => (Key::Backspace, Modifiers::CTRL),
+                (0x0F, 0x09) => (Key::Tab, Modifiers::NONE),
+                (_, ascii) => (Key::Char(ascii), Modifiers::NONE),
+            };
+        }
+        let alt_letter = |scan: u8| match ALT_LETTERS[(scan - 0x10) as usize] {
+            0 => Key::Unknown,
+            letter => Key::Char(letter),
+        };
+        match self.scan_code {
+            0x01 => (Key::Escape, Modifiers::ALT),
+            0x0E => (Key::Backspace, Modifiers::ALT),
+            0x0F => (Key::Tab, Modifiers::SHIFT),
+            0x1C | 0xA6 => (Key::Enter, Modifiers::ALT),
+            0x10 ..= 0x32 => (alt_letter(self.scan_code), Modifiers::ALT),
+            0x3B ..= 0x44 => (Key::F(self.scan_code - 0x3B + 1), Modifiers::NONE),
+            0x47 => (Key::Home, Modifiers::NONE),
+            0x48 => (Key::Up, Modifiers::NONE),
+            0x49 => (Key::PageUp, Modifiers::NONE),
+            0x4B => (Key::Left, Modifiers::NONE),
+            0x4C => (Key::Center, Modifiers::NONE),
+            0x4D => (Key::Right, Modifiers::NONE),
+            0x4F => (Key::End, Modifiers::NONE),
+            0x50 => (Key::Down, Modifiers::NONE),
+            0x51 => (Key::PageDown, Modifiers::NONE),
+            0x52 => (Key::Insert, Modifiers::NONE),
+            0x53 => (Key::Delete, Modifiers::NONE),
+            0x54 ..= 0x5D => (Key::F(self.scan_code - 0x54 + 1), Modifiers::SHIFT),
+            0x5E ..= 0x67 => (Key::F(self.scan_code - 0x5E + 1), Modifiers::CTRL),
+            0x68 ..= 0x71 => (Key::F(self.scan_code - 0x68 + 1), Modifiers::ALT),
+            0x73 => (Key::Left, Modifiers::CTRL),
+            0x74 => (Key::Right, Modifiers::CTRL),
+            0x75 => (Key::End, Modifiers::CTRL),
+            0x76 => (Key::PageDown, Modifiers::CTRL),
+            0x77 => (Key::Home, Modifiers::CTRL),
+            0x78 ..= 0x80 => (Key::Char(b'1' + self.scan_code - 0x78), Modifiers::ALT),
+            0x81 => (Key::Char(b'0'), Modifiers::ALT),
+            0x82 => (Key::Char(b'-'), Modifiers::ALT),
+            0x83 => (Key::Char(b'='), Modifiers::ALT),
+            0x84 => (Key::PageUp, Modifiers::CTRL),
+            0x85 | 0x86 => (Key::F(self.scan_code - 0x85 + 11), Modifiers::NONE),
+            0x87 | 0x88 => (Key::F(self.scan_code - 0x87 + 11), Modifiers::SHIFT),
+            0x89 | 0x8A => (Key::F(self.scan_code - 0x89 + 11), Modifiers::CTRL),
+            0x8B | 0x8C => (Key::F(self.scan_code - 0x8B + 11), Modifiers::ALT),
+            0x8D => (Key::Up, Modifiers::CTRL),
+            0x8F => (Key::Center, Modifiers::CTRL),
+            0x91 => (Key::Down, Modifiers::CTRL),
+            0x92 => (Key::Insert, Modifiers::CTRL),
+            0x93 => (Key::Delete, Modifiers::CTRL),
+            0x94 => (Key::Tab, Modifiers::CTRL),
+            0x97 => (Key::Home, Modifiers::ALT),
+            0x98 => (Key::Up, Modifiers::ALT),
+            0x99 => (Key::PageUp, Modifiers::ALT),
+            0x9B => (Key::Left, Modifiers::ALT),
+            0x9D => (Key::Right, Modifiers::ALT),
+            0x9F => (Key::End, Modifiers::ALT),
+            0xA0 => (Key::Down, Modifiers::ALT),
+            0xA1 => (Key::PageDown, Modifiers::ALT),
+            0xA2 => (Key::Insert, Modifiers::ALT),
+            0xA3 => (Key::Delete, Modifiers::ALT),
+            0xA5 => (Key::Tab, Modifiers::ALT),
+            _ => (Key::Unknown, Modifiers::NONE),
+        }
+    }
+}
+
+/// Checks the BIOS data area for a 101/102-key keyboard, which the enhanced services require.
+pub fn has_enhanced_keyboard() -> bool {
+    FarSlice::rm_segment(BDA_SEGMENT)
+        .is_ok_and(|bda| bda.read_u8(BDA_KEYBOARD_STATUS_3) & ENHANCED_KEYBOARD != 0)
+}
+
+pub fn read_key() -> KeyEvent {
+    if has_enhanced_keyboard() {
+        int_16h_ah_10h_read_key_ext().into()
+    } else {
+        int_16h_ah_00h_read_key().into()
+    }
+}
+
+pub fn peek_key() -> Result<Option<KeyEvent>, DpmiErr> {
+    let key = if has_enhanced_keyboard() { int_16h_ah_11h_peek_key_ext()? } else { int_16h_ah_01h_peek_key()? };
+    Ok(key.map(KeyEvent::from))
+}
+
+pub fn poll_key() -> Result<Option<KeyEvent>, DpmiErr> {
+    Ok(if peek_key()?.is_some() { Some(read_key()) } else { None })
+}
+
+pub fn stuff_key(key: KeyEvent) -> Result<(), AlErr> {
+    int_16h_ah_05h_stuff_key(key.scan_code, key.ascii)
+}
+
+pub fn shift_flags() -> u16 {
+    if has_enhanced_keyboard() {
+        int_16h_ah_12h_shift_flags_ext().ax_shift_flags
+    } else {
+        int_16h_ah_02h_shift_flags().al_shift_flags as u16
+    }
+}
+
+pub fn modifiers() -> Modifiers {
+    Modifiers::from_shift_flags(shift_flags())
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum TypematicDelay {
+    Ms250 = 0,
+    Ms500 = 1,
+    Ms750 = 2,
+    Ms1000 = 3,
+}
+
+/// Sets the autorepeat delay and rate, where rate 0 is 30 repeats per second and 1Fh is 2.
+pub fn set_typematic(delay: TypematicDelay, rate: u8) {
+    assert!(rate <= 0x1F);
+    int_16h_ax_0305h_set_typematic(delay as u8, rate);
+}
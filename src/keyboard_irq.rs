@@ -0,0 +1,210 @@
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, Ordering};
+use crate::*;
+use crate::isr::*;
+
+pub const KEYBOARD_DATA: u16 = 0x60;
+pub const KEYBOARD_IRQ: u8 = 1;
+
+const QUEUE_LEN: usize = 64;
+
+/// Bytes following E1 in the Pause sequence `E1 1D 45 E1 9D C5`.
+const PAUSE_TAIL_LEN: u8 = 5;
+
+const EVENT_PRESSED: u16 = 0x100;
+
+/// A key in scancode set 1: the make code, with bit 7 set for keys sent after an E0 prefix.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct KeyCode(pub u8);
+
+impl KeyCode {
+    pub const ESCAPE: KeyCode = KeyCode(0x01);
+    pub const BACKSPACE: KeyCode = KeyCode(0x0E);
+    pub const TAB: KeyCode = KeyCode(0x0F);
+    pub const ENTER: KeyCode = KeyCode(0x1C);
+    pub const LEFT_CTRL: KeyCode = KeyCode(0x1D);
+    pub const LEFT_SHIFT: KeyCode = KeyCode(0x2A);
+    pub const RIGHT_SHIFT: KeyCode = KeyCode(0x36);
+    pub const LEFT_ALT: KeyCode = KeyCode(0x38);
+    pub const SPACE: KeyCode = KeyCode(0x39);
+    pub const CAPS_LOCK: KeyCode = KeyCode(0x3A);
+    pub const F1: KeyCode = KeyCode(0x3B);
+    pub const F10: KeyCode = KeyCode(0x44);
+    pub const NUM_LOCK: KeyCode = KeyCode(0x45);
+    pub const SCROLL_LOCK: KeyCode = KeyCode(0x46);
+    pub const F11: KeyCode = KeyCode(0x57);
+    pub const F12: KeyCode = KeyCode(0x58);
+    pub const KEYPAD_ENTER: KeyCode = KeyCode(0x9C);
+    pub const RIGHT_CTRL: KeyCode = KeyCode(0x9D);
+    pub const KEYPAD_SLASH: KeyCode = KeyCode(0xB5);
+    pub const PRINT_SCREEN: KeyCode = KeyCode(0xB7);
+    pub const RIGHT_ALT: KeyCode = KeyCode(0xB8);
+    /// Ctrl+Pause, which the keyboard sends as `E0 46`.
+    pub const BREAK: KeyCode = KeyCode(0xC6);
+    /// Pause has no E0 form of its own, so it takes the free slot at `E0 45`.
+    pub const PAUSE: KeyCode = KeyCode(0xC5);
+    pub const HOME: KeyCode = KeyCode(0xC7);
+    pub const UP: KeyCode = KeyCode(0xC8);
+    pub const PAGE_UP: KeyCode = KeyCode(0xC9);
+    pub const LEFT: KeyCode = KeyCode(0xCB);
+    pub const RIGHT: KeyCode = KeyCode(0xCD);
+    pub const END: KeyCode = KeyCode(0xCF);
+    pub const DOWN: KeyCode = KeyCode(0xD0);
+    pub const PAGE_DOWN: KeyCode = KeyCode(0xD1);
+    pub const INSERT: KeyCode = KeyCode(0xD2);
+    pub const DELETE: KeyCode = KeyCode(0xD3);
+    pub const LEFT_WIN: KeyCode = KeyCode(0xDB);
+    pub const RIGHT_WIN: KeyCode = KeyCode(0xDC);
+    pub const MENU: KeyCode = KeyCode(0xDD);
+
+    pub const fn is_extended(self) -> bool { self.0 & 0x80 != 0 }
+
+    pub const fn make_code(self) -> u8 { self.0 & 0x7F }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct KeyChange {
+    pub key: KeyCode,
+    pub pressed: bool,
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static VECTOR: AtomicU8 = AtomicU8::new(0);
+static CHAIN_TO_BIOS: AtomicBool = AtomicBool::new(false);
+static E0_PREFIX: AtomicBool = AtomicBool::new(false);
+static PAUSE_LEFT: AtomicU8 = AtomicU8::new(0);
+static PRESSED: [AtomicU32; 8] = [const { AtomicU32::new(0) }; 8];
+static QUEUE: [AtomicU16; QUEUE_LEN] = [const { AtomicU16::new(0) }; QUEUE_LEN];
+static QUEUE_HEAD: AtomicU8 = AtomicU8::new(0);
+static QUEUE_TAIL: AtomicU8 = AtomicU8::new(0);
+static OLD_HANDLER: IsrChain = IsrChain::new();
+static STACK: IsrStack = IsrStack::new();
+
+fn push_event(key: KeyCode, pressed: bool) {
+    let tail = QUEUE_TAIL.load(Ordering::Relaxed);
+    let next = (tail + 1) % QUEUE_LEN as u8;
+    if next == QUEUE_HEAD.load(Ordering::Acquire) { return; }
+    let event = if pressed { EVENT_PRESSED } else { 0 } | key.0 as u16;
+    QUEUE[tail as usize].store(event, Ordering::Relaxed);
+    QUEUE_TAIL.store(next, Ordering::Release);
+}
+
+fn set_pressed(key: KeyCode, pressed: bool) {
+    let bit = 1 << (key.0 % 32);
+    let word = &PRESSED[(key.0 / 32) as usize];
+    if pressed {
+        word.fetch_or(bit, Ordering::Relaxed);
+    } else {
+        word.fetch_and(!bit, Ordering::Relaxed);
+    }
+}
+
+fn decode(byte: u8) {
+    let pause_left = PAUSE_LEFT.load(Ordering::Relaxed);
+    if pause_left != 0 {
+        PAUSE_LEFT.store(pause_left - 1, Ordering::Relaxed);
+        if pause_left == 1 {
+            push_event(KeyCode::PAUSE, true);
+            push_event(KeyCode::PAUSE, false);
+        }
+        return;
+    }
+    match byte {
+        0xE0 => { E0_PREFIX.store(true, Ordering::Relaxed); return; },
+        0xE1 => { PAUSE_LEFT.store(PAUSE_TAIL_LEN, Ordering::Relaxed); return; },
+        // Keyboard acknowledgements, resend requests and buffer overruns.
+        0x00 | 0xFA | 0xFE | 0xFF => return,
+        _ => { },
+    }
+    let extended = E0_PREFIX.swap(false, Ordering::Relaxed);
+    let make_code = byte & 0x7F;
+    // E0 2A and E0 36 are fake shifts wrapped around gray keys; they carry no key of their own.
+    if extended && (make_code == 0x2A || make_code == 0x36) { return; }
+    let key = KeyCode(make_code | if extended { 0x80 } else { 0 });
+    let pressed = byte & 0x80 == 0;
+    let was_pressed = is_key_pressed(key);
+    set_pressed(key, pressed);
+    if pressed || was_pressed {
+        push_event(key, pressed);
+    }
+}
+
+fn is_key_pressed(key: KeyCode) -> bool {
+    PRESSED[(key.0 / 32) as usize].load(Ordering::Relaxed) & (1 << (key.0 % 32)) != 0
+}
+
+extern "C" fn keyboard_isr() -> u32 {
+    decode(unsafe { port_in_u8(KEYBOARD_DATA) });
+    if CHAIN_TO_BIOS.load(Ordering::Relaxed) {
+        1
+    } else {
+        pic_eoi();
+        0
+    }
+}
+
+isr_entry!(keyboard_isr_entry, keyboard_isr, STACK, OLD_HANDLER);
+
+/// Owns the IRQ1 vector while alive. Only one driver can be installed at a time.
+pub struct KeyboardDriver(());
+
+impl KeyboardDriver {
+    /// With `chain_to_bios`, every scan code also goes on to the previous handler,
+    /// so BIOS keyboard services and shift states keep working.
+    pub fn install(chain_to_bios: bool) -> Result<KeyboardDriver, InstallErr> {
+        isr_init()?;
+        if INSTALLED.swap(true, Ordering::Acquire) { return Err(InstallErr::AlreadyInstalled); }
+        E0_PREFIX.store(false, Ordering::Relaxed);
+        for data in [&PAUSE_LEFT, &QUEUE_HEAD, &QUEUE_TAIL] {
+            data.store(0, Ordering::Relaxed);
+        }
+        for word in &PRESSED {
+            word.store(0, Ordering::Relaxed);
+        }
+        CHAIN_TO_BIOS.store(chain_to_bios, Ordering::Relaxed);
+        lock_data(&CHAIN_TO_BIOS);
+        lock_data(&E0_PREFIX);
+        lock_data(&PAUSE_LEFT);
+        lock_data(&PRESSED);
+        lock_data(&QUEUE);
+        lock_data(&QUEUE_HEAD);
+        lock_data(&QUEUE_TAIL);
+        lock_data(&OLD_HANDLER);
+        lock_data(&STACK);
+        let vector = irq_vector(KEYBOARD_IRQ);
+        VECTOR.store(vector, Ordering::Relaxed);
+        OLD_HANDLER.set(int_21h_ah_35h_get_int(vector).es_ebx_int_handler);
+        int_21h_ah_25h_set_int(vector, PmFarPtr::new(code_selector(), keyboard_isr_entry as *const () as usize as u32));
+        Ok(KeyboardDriver(()))
+    }
+
+    pub fn is_pressed(&self, key: KeyCode) -> bool {
+        is_key_pressed(key)
+    }
+
+    /// Takes the oldest key change from the queue. Changes arriving while it is full are dropped.
+    pub fn next_event(&self) -> Option<KeyChange> {
+        let head = QUEUE_HEAD.load(Ordering::Relaxed);
+        if head == QUEUE_TAIL.load(Ordering::Acquire) { return None; }
+        let event = QUEUE[head as usize].load(Ordering::Relaxed);
+        QUEUE_HEAD.store((head + 1) % QUEUE_LEN as u8, Ordering::Release);
+        Some(KeyChange { key: KeyCode(event as u8), pressed: event & EVENT_PRESSED != 0 })
+    }
+
+    pub fn clear_events(&self) {
+        QUEUE_HEAD.store(QUEUE_TAIL.load(Ordering::Acquire), Ordering::Release);
+    }
+}
+
+/// Puts the original IRQ1 handler back if the driver is installed.
+pub(crate) fn uninstall() {
+    if INSTALLED.load(Ordering::Acquire) {
+        int_21h_ah_25h_set_int(VECTOR.load(Ordering::Relaxed), OLD_HANDLER.get());
+        INSTALLED.store(false, Ordering::Release);
+    }
+}
+
+impl Drop for KeyboardDriver {
+    fn drop(&mut self) {
+        uninstall();
+    }
+}
@@ -8,12 +8,38 @@
 
 #[cfg(dos)]
 use core::arch::asm;
+use core::fmt::{self, Debug, Formatter};
 use core::mem::MaybeUninit;
 #[cfg(dos)]
 use core::mem::size_of;
 use core::num::NonZeroU8;
-#[cfg(dos)]
-use memoffset::offset_of;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+
+pub mod adapter;
+pub mod compositor;
+pub mod country;
+pub mod critical_error;
+pub mod ctrl_break;
+pub mod date_time;
+pub mod far_mem;
+pub mod font;
+pub mod gfx;
+pub mod isr;
+pub mod keyboard;
+pub mod keyboard_irq;
+pub mod mouse;
+pub mod palette;
+pub mod pit;
+pub mod raw_switch;
+pub mod rtc;
+pub mod speaker;
+pub mod text;
+pub mod time;
+pub mod timer_irq;
+pub mod vbe;
+pub mod vga;
+pub mod video_state;
+pub mod xfer;
 
 pub const DOS_ERR_FUNC_NUM_INVALID: u8 = 1;
 pub const DOS_ERR_FILE_NOT_FOUND: u8 = 2;
@@ -206,7 +232,6 @@ pub struct AxErr {
     pub ax_err: u16,
 }
 
-#[cfg(dos)]
 const CF: u8 = 0x01;
 
 #[cfg(dos)]
@@ -506,49 +531,40 @@ pub struct AlChar {
     pub al_char: u8,
 }
 
-#[cfg(not(dos))]
-#[allow(non_snake_case)]
-pub fn int_21h_ah_06h_dl_FFh_inkey() -> Result<Option<AlChar>, DpmiErr> {
-    panic!("cfg(target_os=\"dos\")");
-}
-
-#[cfg(dos)]
+#[derive(Debug, Clone, Default)]
 #[repr(C)]
-struct RmRegs {
-    edi: u32,
-    esi: u32,
-    ebp: u32,
-    zero: u32,
-    ebx: u32,
-    edx: u32,
-    ecx: u32,
-    eax: u32,
-    flags: u16,
-    es: u16,
-    ds: u16,
-    fs: u16,
-    gs: u16,
-    ip: u16,
-    cs: u16,
-    sp: u16,
-    ss: u16,
+pub struct RmRegs {
+    pub edi: u32,
+    pub esi: u32,
+    pub ebp: u32,
+    pub zero: u32,
+    pub ebx: u32,
+    pub edx: u32,
+    pub ecx: u32,
+    pub eax: u32,
+    pub flags: u16,
+    pub es: u16,
+    pub ds: u16,
+    pub fs: u16,
+    pub gs: u16,
+    pub ip: u16,
+    pub cs: u16,
+    pub sp: u16,
+    pub ss: u16,
 }
 
+#[derive(Debug, Clone)]
 pub struct DpmiErr(pub u16);
 
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_31h_ax_0300h_simulate_rm_int(bl_int_num: u8, edi_regs: &mut RmRegs) -> Result<(), DpmiErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
 #[cfg(dos)]
-#[allow(non_snake_case)]
 #[inline]
-pub fn int_21h_ah_06h_dl_FFh_inkey() -> Result<Option<AlChar>, DpmiErr> {
-    let mut regs = RmRegs {
-        es: 0, ds: 0, fs: 0, gs: 0, ip: 0, cs: 0, sp: 0, ss: 0,
-        edi: 0, esi: 0, ebp: 0, zero: 0, ebx: 0, ecx: 0, flags: 0,
-        edx: 0x00FF,
-        eax: 0x0600,
-    };
-    let regs = &mut regs;
-    let regs = regs as *mut _;
-    let mut regs = p32(regs);
+pub fn int_31h_ax_0300h_simulate_rm_int(bl_int_num: u8, edi_regs: &mut RmRegs) -> Result<(), DpmiErr> {
     let mut flags: u16;
     let mut ax_err: u16;
     unsafe {
@@ -558,51 +574,339 @@ pub fn int_21h_ah_06h_dl_FFh_inkey() -> Result<Option<AlChar>, DpmiErr> {
             "lahf",
             ax_err = lateout(reg) ax_err,
             in("ax") 0x0300u16,
-            in("bx") 0x0021u16,
+            in("bx") bl_int_num as u16,
             in("cx") 0x0000u16,
-            inlateout("edi") regs => regs,
+            inlateout("edi") p32(edi_regs) => _,
             lateout("ax") flags,
         );
     }
     if ((flags >> 8) as u8) & CF == 0 {
-        let mut rm_flags: u16;
-        let mut rm_eax: u32;
-        unsafe {
-            asm!(
-                "mov {rm_flags:x}, [{regs:e} + {regs_flags_offset}]",
-                "mov {rm_eax:e}, [{regs:e} + {regs_eax_offset}]",
-                regs = in(reg) regs,
-                rm_flags = out(reg) rm_flags,
-                rm_eax = out(reg) rm_eax,
-                regs_flags_offset = const offset_of!(RmRegs, flags),
-                regs_eax_offset = const offset_of!(RmRegs, eax),
-            );
-        }
-        if rm_flags & u16::from(ZF) == 0 {
-            Ok(Some(AlChar { al_char: rm_eax as u8 }))
-        } else {
-            Ok(None)
-        }
+        Ok(())
     } else {
         Err(DpmiErr(ax_err))
     }
 }
 
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+pub fn int_21h_ah_06h_dl_FFh_inkey() -> Result<Option<AlChar>, DpmiErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_21h_ah_06h_dl_FFh_inkey() -> Result<Option<AlChar>, DpmiErr> {
+    let mut regs = RmRegs {
+        edx: 0x00FF,
+        eax: 0x0600,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x21, &mut regs)?;
+    if regs.flags & u16::from(ZF) == 0 {
+        Ok(Some(AlChar { al_char: regs.eax as u8 }))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+fn int_21h_read_ch(ax_func: u16) -> AlChar {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+fn int_21h_read_ch(ax_func: u16) -> AlChar {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x21",
+            inlateout("ax") ax_func => ax,
+        );
+    }
+    AlChar { al_char: ax as u8 }
+}
+
+pub fn int_21h_ah_01h_read_ch_echo() -> AlChar {
+    int_21h_read_ch(0x0100)
+}
+
+pub fn int_21h_ah_07h_read_ch_raw() -> AlChar {
+    int_21h_read_ch(0x0700)
+}
+
+pub fn int_21h_ah_08h_read_ch() -> AlChar {
+    int_21h_read_ch(0x0800)
+}
+
+#[repr(C)]
+#[derive(Clone)]
+pub struct LineBuf {
+    max: u8,
+    len: u8,
+    buf: [u8; 255],
+}
+
+impl LineBuf {
+    /// Creates a buffer for lines of 1 to 254 characters, not counting the final CR.
+    pub const fn new(max_len: u8) -> LineBuf {
+        assert!(max_len != 0 && max_len < 255);
+        LineBuf { max: max_len + 1, len: 0, buf: [0; 255] }
+    }
+
+    pub fn max_len(&self) -> u8 { self.max - 1 }
+
+    pub fn line(&self) -> &[u8] { &self.buf[.. self.len as usize] }
+
+    pub fn clear(&mut self) { self.len = 0; }
+}
+
+impl Default for LineBuf {
+    fn default() -> LineBuf { LineBuf::new(254) }
+}
+
+impl Debug for LineBuf {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("LineBuf").field("max_len", &self.max_len()).field("line", &self.line()).finish()
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+#[allow(unused_variables)]
+fn int_21h_read_line(ax_func: u16, dx_buf: &mut LineBuf) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+fn int_21h_read_line(ax_func: u16, dx_buf: &mut LineBuf) {
+    dx_buf.len = 0;
+    unsafe {
+        asm!(
+            "int 0x21",
+            inlateout("ax") ax_func => _,
+            in("edx") p32(dx_buf),
+        );
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn int_21h_ah_0Ah_read_line(dx_buf: &mut LineBuf) {
+    int_21h_read_line(0x0A00, dx_buf);
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+pub fn int_21h_ah_0Bh_input_status() -> bool {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_21h_ah_0Bh_input_status() -> bool {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x21",
+            inlateout("ax") 0x0B00u16 => ax,
+        );
+    }
+    ax as u8 != 0
+}
+
+#[allow(non_snake_case)]
+pub fn int_21h_ax_0C00h_flush() {
+    let _ = int_21h_read_ch(0x0C00);
+}
+
+#[allow(non_snake_case)]
+pub fn int_21h_ax_0C01h_flush_read_ch_echo() -> AlChar {
+    int_21h_read_ch(0x0C01)
+}
+
+#[allow(non_snake_case)]
+pub fn int_21h_ax_0C07h_flush_read_ch_raw() -> AlChar {
+    int_21h_read_ch(0x0C07)
+}
+
+#[allow(non_snake_case)]
+pub fn int_21h_ax_0C08h_flush_read_ch() -> AlChar {
+    int_21h_read_ch(0x0C08)
+}
+
+#[allow(non_snake_case)]
+pub fn int_21h_ax_0C0Ah_flush_read_line(dx_buf: &mut LineBuf) {
+    int_21h_read_line(0x0C0A, dx_buf);
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct RmFarPtr {
+    pub segment: u16,
+    pub offset: u16,
+}
+
+/// The last byte real mode reaches, FFFF:FFFF at the top of the HMA.
+pub const RM_MAX_LINEAR: u32 = 0x10FFEF;
+
+impl RmFarPtr {
+    pub const fn new(segment: u16, offset: u16) -> Self {
+        RmFarPtr { segment, offset }
+    }
+
+    pub const fn from_u32(segment_offset: u32) -> Self {
+        RmFarPtr { segment: (segment_offset >> 16) as u16, offset: segment_offset as u16 }
+    }
+
+    pub const fn to_u32(self) -> u32 {
+        ((self.segment as u32) << 16) | self.offset as u32
+    }
+
+    pub const fn linear(self) -> u32 {
+        ((self.segment as u32) << 4) + self.offset as u32
+    }
+
+    /// Prefers offsets below 10h, except in the HMA, which only segment FFFFh reaches.
+    pub const fn from_linear(linear: u32) -> Option<Self> {
+        if linear > RM_MAX_LINEAR { return None; }
+        if linear > 0xFFFFF {
+            return Some(RmFarPtr { segment: 0xFFFF, offset: (linear - 0xFFFF0) as u16 });
+        }
+        Some(RmFarPtr { segment: (linear >> 4) as u16, offset: (linear & 0xF) as u16 })
+    }
+
+    pub const fn normalize(self) -> Option<Self> {
+        RmFarPtr::from_linear(self.linear())
+    }
+
+    pub const fn checked_add(self, n: u32) -> Option<Self> {
+        match self.linear().checked_add(n) {
+            Some(linear) => RmFarPtr::from_linear(linear),
+            None => None,
+        }
+    }
+
+    pub const fn checked_sub(self, n: u32) -> Option<Self> {
+        match self.linear().checked_sub(n) {
+            Some(linear) => RmFarPtr::from_linear(linear),
+            None => None,
+        }
+    }
+}
+
+impl Debug for RmFarPtr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:04X}:{:04X}", self.segment, self.offset)
+    }
+}
+
+/// Wraps the offset within the segment, as real-mode addressing does;
+/// [`RmFarPtr::checked_add`] moves across segments instead.
+impl Add<u16> for RmFarPtr {
+    type Output = RmFarPtr;
+
+    fn add(self, rhs: u16) -> RmFarPtr {
+        RmFarPtr { segment: self.segment, offset: self.offset.wrapping_add(rhs) }
+    }
+}
+
+impl AddAssign<u16> for RmFarPtr {
+    fn add_assign(&mut self, rhs: u16) {
+        *self = *self + rhs;
+    }
+}
+
+/// Wraps the offset within the segment, as real-mode addressing does;
+/// [`RmFarPtr::checked_sub`] moves across segments instead.
+impl Sub<u16> for RmFarPtr {
+    type Output = RmFarPtr;
+
+    fn sub(self, rhs: u16) -> RmFarPtr {
+        RmFarPtr { segment: self.segment, offset: self.offset.wrapping_sub(rhs) }
+    }
+}
+
+impl SubAssign<u16> for RmFarPtr {
+    fn sub_assign(&mut self, rhs: u16) {
+        *self = *self - rhs;
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[repr(C)]
+pub struct PmFarPtr {
+    pub offset: u32,
+    pub selector: u16,
+}
+
+impl PmFarPtr {
+    pub const fn new(selector: u16, offset: u32) -> Self {
+        PmFarPtr { offset, selector }
+    }
+
+    pub fn linear(self) -> Result<u32, AxErr> {
+        let base = int_31h_ax_0006h_segment_addr(self.selector)?.cx_dx_base;
+        Ok(base.wrapping_add(self.offset))
+    }
+
+    pub fn from_linear(selector: u16, linear: u32) -> Result<Self, AxErr> {
+        let base = int_31h_ax_0006h_segment_addr(selector)?.cx_dx_base;
+        Ok(PmFarPtr { offset: linear.wrapping_sub(base), selector })
+    }
+}
+
+impl Debug for PmFarPtr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:04X}:{:08X}", self.selector, self.offset)
+    }
+}
+
+impl Add<u32> for PmFarPtr {
+    type Output = PmFarPtr;
+
+    fn add(self, rhs: u32) -> PmFarPtr {
+        PmFarPtr { offset: self.offset.wrapping_add(rhs), selector: self.selector }
+    }
+}
+
+impl AddAssign<u32> for PmFarPtr {
+    fn add_assign(&mut self, rhs: u32) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<u32> for PmFarPtr {
+    type Output = PmFarPtr;
+
+    fn sub(self, rhs: u32) -> PmFarPtr {
+        PmFarPtr { offset: self.offset.wrapping_sub(rhs), selector: self.selector }
+    }
+}
+
+impl SubAssign<u32> for PmFarPtr {
+    fn sub_assign(&mut self, rhs: u32) {
+        *self = *self - rhs;
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct CxDxAddr {
-    pub cx_segment: u16,
-    pub dx_offset: u16,
+pub struct CxDxBase {
+    pub cx_dx_base: u32,
 }
 
 #[cfg(not(dos))]
 #[allow(unused_variables)]
-pub fn int_31h_ax_0006h_segment_addr(bx_selector: u16) -> Result<CxDxAddr, AxErr> {
+pub fn int_31h_ax_0006h_segment_addr(bx_selector: u16) -> Result<CxDxBase, AxErr> {
     panic!("cfg(target_os=\"dos\")");
 }
 
 #[cfg(dos)]
 #[inline]
-pub fn int_31h_ax_0006h_segment_addr(bx_selector: u16) -> Result<CxDxAddr, AxErr> {
+pub fn int_31h_ax_0006h_segment_addr(bx_selector: u16) -> Result<CxDxBase, AxErr> {
     let mut flags: u16;
     let mut ax_err: u16;
     let mut cx: u16;
@@ -621,12 +925,51 @@ pub fn int_31h_ax_0006h_segment_addr(bx_selector: u16) -> Result<CxDxAddr, AxErr
         );
     }
     if ((flags >> 8) as u8) & CF == 0 {
-        Ok(CxDxAddr { cx_segment: cx, dx_offset: dx })
+        Ok(CxDxBase { cx_dx_base: ((cx as u32) << 16) | dx as u32 })
     } else {
         Err(AxErr { ax_err })
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct AxSelector {
+    pub ax_selector: u16,
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_31h_ax_0002h_segment_to_descriptor(bx_segment: u16) -> Result<AxSelector, AxErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_31h_ax_0002h_segment_to_descriptor(bx_segment: u16) -> Result<AxSelector, AxErr> {
+    let mut flags: u16;
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x31",
+            "mov {ax:x}, ax",
+            "lahf",
+            ax = lateout(reg) ax,
+            in("ax") 0x0002u16,
+            in("bx") bx_segment,
+            lateout("ax") flags,
+        );
+    }
+    if ((flags >> 8) as u8) & CF == 0 {
+        Ok(AxSelector { ax_selector: ax })
+    } else {
+        Err(AxErr { ax_err: ax })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CxDxAddr {
+    pub cx_dx_addr: RmFarPtr,
+}
+
 #[cfg(not(dos))]
 #[allow(unused_variables)]
 pub fn int_31h_ax_0200h_get_rm_int(bl_vec_num: u8) -> CxDxAddr {
@@ -647,25 +990,25 @@ pub fn int_31h_ax_0200h_get_rm_int(bl_vec_num: u8) -> CxDxAddr {
             lateout("dx") dx,
         );
     }
-    CxDxAddr { cx_segment: cx, dx_offset: dx }
+    CxDxAddr { cx_dx_addr: RmFarPtr { segment: cx, offset: dx } }
 }
 
 #[cfg(not(dos))]
 #[allow(unused_variables)]
-pub fn int_31h_ax_0201h_set_rm_int(bl_vec_num: u8, cx_int_handler_segment: u16, dx_int_handler_offset: u16) {
+pub fn int_31h_ax_0201h_set_rm_int(bl_vec_num: u8, cx_dx_int_handler: RmFarPtr) {
     panic!("cfg(target_os=\"dos\")");
 }
 
 #[cfg(dos)]
 #[inline]
-pub fn int_31h_ax_0201h_set_rm_int(bl_vec_num: u8, cx_int_handler_segment: u16, dx_int_handler_offset: u16) {
+pub fn int_31h_ax_0201h_set_rm_int(bl_vec_num: u8, cx_dx_int_handler: RmFarPtr) {
     unsafe {
         asm!(
             "int 0x31",
             in("ax") 0x0201u16,
             in("bx") bl_vec_num as u16,
-            in("cx") cx_int_handler_segment,
-            in("dx") dx_int_handler_offset,
+            in("cx") cx_dx_int_handler.segment,
+            in("dx") cx_dx_int_handler.offset,
         );
     }
 }
@@ -737,66 +1080,309 @@ pub fn int_31h_ax_0101h_rm_free(dx_selector: u16) -> Result<(), AxErr> {
 }
 
 #[derive(Debug, Clone)]
-pub struct AlErr {
-    pub al_err: NonZeroU8,
+pub struct StateSaveAddrs {
+    pub ax_buf_size: u16,
+    pub bx_cx_rm_addr: RmFarPtr,
+    pub si_edi_pm_addr: PmFarPtr,
 }
 
 #[cfg(not(dos))]
-#[allow(unused_variables)]
-pub fn int_10h_ah_00h_set_video_mode(al_mode: u8) -> Result<(), AlErr> {
+pub fn int_31h_ax_0305h_state_save_addrs() -> StateSaveAddrs {
     panic!("cfg(target_os=\"dos\")");
 }
 
 #[cfg(dos)]
 #[inline]
-pub fn int_10h_ah_00h_set_video_mode(al_mode: u8) -> Result<(), AlErr> {
-    let mut al_err: u16;
+pub fn int_31h_ax_0305h_state_save_addrs() -> StateSaveAddrs {
+    let mut ax_buf_size: u16;
+    let mut bx_rm_segment: u16;
+    let mut cx_rm_offset: u16;
+    let mut si_pm_selector: u16;
+    let mut edi_pm_offset: u32;
     unsafe {
         asm!(
-            "int 0x10",
-            in("ax") al_mode as u16,
-            lateout("ax") al_err,
+            "push esi",
+            "int 0x31",
+            "mov {si_pm_selector:x}, si",
+            "pop esi",
+            si_pm_selector = lateout(reg) si_pm_selector,
+            in("ax") 0x0305u16,
+            lateout("ax") ax_buf_size,
+            lateout("bx") bx_rm_segment,
+            lateout("cx") cx_rm_offset,
+            lateout("edi") edi_pm_offset,
         );
     }
-    NonZeroU8::new(al_err as u8).map(|al_err| AlErr { al_err }).map_or(Ok(()), Err)
+    StateSaveAddrs {
+        ax_buf_size,
+        bx_cx_rm_addr: RmFarPtr { segment: bx_rm_segment, offset: cx_rm_offset },
+        si_edi_pm_addr: PmFarPtr { offset: edi_pm_offset, selector: si_pm_selector },
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RawSwitchAddrs {
+    pub bx_cx_rm_to_pm: RmFarPtr,
+    pub si_edi_pm_to_rm: PmFarPtr,
 }
 
 #[cfg(not(dos))]
-#[allow(unused_variables)]
-pub fn int_10h_ah_05h_set_video_active_page(al_active_page: u8) {
+pub fn int_31h_ax_0306h_raw_switch_addrs() -> RawSwitchAddrs {
     panic!("cfg(target_os=\"dos\")");
 }
 
 #[cfg(dos)]
 #[inline]
-pub fn int_10h_ah_05h_set_video_active_page(al_active_page: u8) {
+pub fn int_31h_ax_0306h_raw_switch_addrs() -> RawSwitchAddrs {
+    let mut bx_rm_to_pm_segment: u16;
+    let mut cx_rm_to_pm_offset: u16;
+    let mut si_pm_to_rm_selector: u16;
+    let mut edi_pm_to_rm_offset: u32;
     unsafe {
         asm!(
-            "int 0x10",
-            in("ax") 0x0500 | al_active_page as u16,
+            "push esi",
+            "int 0x31",
+            "mov {si_pm_to_rm_selector:x}, si",
+            "pop esi",
+            si_pm_to_rm_selector = lateout(reg) si_pm_to_rm_selector,
+            in("ax") 0x0306u16,
+            lateout("ax") _,
+            lateout("bx") bx_rm_to_pm_segment,
+            lateout("cx") cx_rm_to_pm_offset,
+            lateout("edi") edi_pm_to_rm_offset,
         );
     }
+    RawSwitchAddrs {
+        bx_cx_rm_to_pm: RmFarPtr { segment: bx_rm_to_pm_segment, offset: cx_rm_to_pm_offset },
+        si_edi_pm_to_rm: PmFarPtr { offset: edi_pm_to_rm_offset, selector: si_pm_to_rm_selector },
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct VideoMode {
-    pub al_mode: u8,
-    pub ah_cols: u8,
-    pub bh_active_page: u8,
+pub struct RmCallback {
+    pub cx_dx_rm_addr: RmFarPtr,
 }
 
 #[cfg(not(dos))]
-#[allow(non_snake_case)]
 #[allow(unused_variables)]
-pub fn int_10h_ah_0Fh_video_mode() -> VideoMode {
+pub fn int_31h_ax_0303h_alloc_rm_callback(ds_esi_proc: PmFarPtr, es_edi_regs: PmFarPtr) -> Result<RmCallback, AxErr> {
     panic!("cfg(target_os=\"dos\")");
 }
 
 #[cfg(dos)]
-#[allow(non_snake_case)]
 #[inline]
-pub fn int_10h_ah_0Fh_video_mode() -> VideoMode {
-    let mut ax: u16;
+pub fn int_31h_ax_0303h_alloc_rm_callback(ds_esi_proc: PmFarPtr, es_edi_regs: PmFarPtr) -> Result<RmCallback, AxErr> {
+    let mut flags: u16;
+    let mut ax_err: u16;
+    let mut cx_rm_segment: u16;
+    let mut dx_rm_offset: u16;
+    unsafe {
+        asm!(
+            "push ds",
+            "push es",
+            "push esi",
+            "mov es, {es_sel:x}",
+            "mov esi, {esi_proc:e}",
+            "mov ds, {ds_sel:x}",
+            "int 0x31",
+            "pop esi",
+            "pop es",
+            "pop ds",
+            "mov {ax_err:x}, ax",
+            "lahf",
+            ds_sel = in(reg) ds_esi_proc.selector as u32,
+            es_sel = in(reg) es_edi_regs.selector as u32,
+            esi_proc = in(reg) ds_esi_proc.offset,
+            ax_err = lateout(reg) ax_err,
+            in("ax") 0x0303u16,
+            in("edi") es_edi_regs.offset,
+            lateout("ax") flags,
+            lateout("cx") cx_rm_segment,
+            lateout("dx") dx_rm_offset,
+        );
+    }
+    if ((flags >> 8) as u8) & CF == 0 {
+        Ok(RmCallback { cx_dx_rm_addr: RmFarPtr { segment: cx_rm_segment, offset: dx_rm_offset } })
+    } else {
+        Err(AxErr { ax_err })
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_31h_ax_0304h_free_rm_callback(cx_dx_rm_addr: RmFarPtr) -> Result<(), AxErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_31h_ax_0304h_free_rm_callback(cx_dx_rm_addr: RmFarPtr) -> Result<(), AxErr> {
+    let mut flags: u16;
+    let mut ax_err: u16;
+    unsafe {
+        asm!(
+            "int 0x31",
+            "mov {ax_err:x}, ax",
+            "lahf",
+            ax_err = lateout(reg) ax_err,
+            in("ax") 0x0304u16,
+            in("cx") cx_dx_rm_addr.segment,
+            in("dx") cx_dx_rm_addr.offset,
+            lateout("ax") flags,
+        );
+    }
+    if ((flags >> 8) as u8) & CF == 0 {
+        Ok(())
+    } else {
+        Err(AxErr { ax_err })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DpmiVersion {
+    pub ah_major: u8,
+    pub al_minor: u8,
+    pub bx_flags: u16,
+    pub cl_processor: u8,
+    pub dh_master_pic_base: u8,
+    pub dl_slave_pic_base: u8,
+}
+
+#[cfg(not(dos))]
+pub fn int_31h_ax_0400h_version() -> DpmiVersion {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_31h_ax_0400h_version() -> DpmiVersion {
+    let mut ax: u16;
+    let mut bx: u16;
+    let mut cx: u16;
+    let mut dx: u16;
+    unsafe {
+        asm!(
+            "int 0x31",
+            inlateout("ax") 0x0400u16 => ax,
+            lateout("bx") bx,
+            lateout("cx") cx,
+            lateout("dx") dx,
+        );
+    }
+    DpmiVersion {
+        ah_major: (ax >> 8) as u8,
+        al_minor: ax as u8,
+        bx_flags: bx,
+        cl_processor: cx as u8,
+        dh_master_pic_base: (dx >> 8) as u8,
+        dl_slave_pic_base: dx as u8,
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+fn int_31h_linear_region(ax_func: u16, bx_cx_linear: u32, si_di_len: u32) -> Result<(), AxErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+fn int_31h_linear_region(ax_func: u16, bx_cx_linear: u32, si_di_len: u32) -> Result<(), AxErr> {
+    let mut flags: u16;
+    let mut ax_err: u16;
+    unsafe {
+        asm!(
+            "push esi",
+            "mov esi, {si_len:e}",
+            "int 0x31",
+            "pop esi",
+            "mov {ax_err:x}, ax",
+            "lahf",
+            si_len = in(reg) si_di_len >> 16,
+            ax_err = lateout(reg) ax_err,
+            in("ax") ax_func,
+            in("bx") (bx_cx_linear >> 16) as u16,
+            in("cx") bx_cx_linear as u16,
+            in("di") si_di_len as u16,
+            lateout("ax") flags,
+        );
+    }
+    if ((flags >> 8) as u8) & CF == 0 {
+        Ok(())
+    } else {
+        Err(AxErr { ax_err })
+    }
+}
+
+pub fn int_31h_ax_0600h_lock(bx_cx_linear: u32, si_di_len: u32) -> Result<(), AxErr> {
+    int_31h_linear_region(0x0600, bx_cx_linear, si_di_len)
+}
+
+pub fn int_31h_ax_0601h_unlock(bx_cx_linear: u32, si_di_len: u32) -> Result<(), AxErr> {
+    int_31h_linear_region(0x0601, bx_cx_linear, si_di_len)
+}
+
+#[derive(Debug, Clone)]
+pub struct AlErr {
+    pub al_err: NonZeroU8,
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ah_00h_set_video_mode(al_mode: u8) -> Result<(), AlErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ah_00h_set_video_mode(al_mode: u8) -> Result<(), AlErr> {
+    let mut al_err: u16;
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") al_mode as u16,
+            lateout("ax") al_err,
+        );
+    }
+    NonZeroU8::new(al_err as u8).map(|al_err| AlErr { al_err }).map_or(Ok(()), Err)
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ah_05h_set_video_active_page(al_active_page: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ah_05h_set_video_active_page(al_active_page: u8) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x0500 | al_active_page as u16,
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VideoMode {
+    pub al_mode: u8,
+    pub ah_cols: u8,
+    pub bh_active_page: u8,
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+#[allow(unused_variables)]
+pub fn int_10h_ah_0Fh_video_mode() -> VideoMode {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_10h_ah_0Fh_video_mode() -> VideoMode {
+    let mut ax: u16;
     let mut bx_active_page: u16;
     unsafe {
         asm!(
@@ -813,44 +1399,1720 @@ pub fn int_10h_ah_0Fh_video_mode() -> VideoMode {
     }
 }
 
-pub struct IntHandler {
-    pub ebx_int_handler: u32,
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum Color {
+    Black = 0,
+    Blue = 1,
+    Green = 2,
+    Cyan = 3,
+    Red = 4,
+    Magenta = 5,
+    Brown = 6,
+    LightGray = 7,
+    DarkGray = 8,
+    LightBlue = 9,
+    LightGreen = 10,
+    LightCyan = 11,
+    LightRed = 12,
+    LightMagenta = 13,
+    Yellow = 14,
+    White = 15,
+}
+
+impl Color {
+    pub const fn from_u8(value: u8) -> Color {
+        match value & 0x0F {
+            0 => Color::Black,
+            1 => Color::Blue,
+            2 => Color::Green,
+            3 => Color::Cyan,
+            4 => Color::Red,
+            5 => Color::Magenta,
+            6 => Color::Brown,
+            7 => Color::LightGray,
+            8 => Color::DarkGray,
+            9 => Color::LightBlue,
+            10 => Color::LightGreen,
+            11 => Color::LightCyan,
+            12 => Color::LightRed,
+            13 => Color::LightMagenta,
+            14 => Color::Yellow,
+            _ => Color::White,
+        }
+    }
+}
+
+/// Text mode character attribute.
+///
+/// The highest bit is either the blink flag or the background intensity bit,
+/// depending on the current blink/intensity toggle.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Attr(pub u8);
+
+impl Attr {
+    pub const fn new(fg: Color, bg: Color, blink: bool) -> Attr {
+        Attr(((blink as u8) << 7) | (((bg as u8) & 0x07) << 4) | fg as u8)
+    }
+
+    pub const fn fg(self) -> Color {
+        Color::from_u8(self.0)
+    }
+
+    pub const fn bg(self) -> Color {
+        Color::from_u8((self.0 >> 4) & 0x07)
+    }
+
+    pub const fn bright_bg(self) -> Color {
+        Color::from_u8(self.0 >> 4)
+    }
+
+    pub const fn blink(self) -> bool {
+        self.0 & 0x80 != 0
+    }
+
+    pub const fn with_fg(self, fg: Color) -> Attr {
+        Attr((self.0 & 0xF0) | fg as u8)
+    }
+
+    pub const fn with_bg(self, bg: Color) -> Attr {
+        Attr((self.0 & 0x8F) | (((bg as u8) & 0x07) << 4))
+    }
+
+    pub const fn with_bright_bg(self, bg: Color) -> Attr {
+        Attr((self.0 & 0x0F) | ((bg as u8) << 4))
+    }
+
+    pub const fn with_blink(self, blink: bool) -> Attr {
+        Attr((self.0 & 0x7F) | ((blink as u8) << 7))
+    }
 }
 
 #[cfg(not(dos))]
 #[allow(unused_variables)]
-pub fn int_21h_ah_35h_get_int(al_vec_num: u8) -> IntHandler {
+pub fn int_10h_ah_01h_set_cursor_shape(ch_start_line: u8, cl_end_line: u8) {
     panic!("cfg(target_os=\"dos\")");
 }
 
 #[cfg(dos)]
 #[inline]
-pub fn int_21h_ah_35h_get_int(al_vec_num: u8) -> IntHandler {
-    let mut ebx_int_handler: u32;
+pub fn int_10h_ah_01h_set_cursor_shape(ch_start_line: u8, cl_end_line: u8) {
     unsafe {
         asm!(
-            "int 0x21",
-            in("ax") 0x3500u16 | al_vec_num as u16,
-            lateout("ebx") ebx_int_handler,
+            "int 0x10",
+            in("ax") 0x0100u16,
+            in("cx") ((ch_start_line as u16) << 8) | (cl_end_line as u16),
         );
     }
-    IntHandler { ebx_int_handler }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    pub ch_start_line: u8,
+    pub cl_end_line: u8,
+    pub dh_row: u8,
+    pub dl_column: u8,
 }
 
 #[cfg(not(dos))]
 #[allow(unused_variables)]
-pub fn int_21h_ah_25h_set_int(al_vec_num: u8, edx_int_handler: u32) {
+pub fn int_10h_ah_03h_cursor(bh_video_page: u8) -> Cursor {
     panic!("cfg(target_os=\"dos\")");
 }
 
 #[cfg(dos)]
 #[inline]
-pub fn int_21h_ah_25h_set_int(al_vec_num: u8, edx_int_handler: u32) {
+pub fn int_10h_ah_03h_cursor(bh_video_page: u8) -> Cursor {
+    let mut cx: u16;
+    let mut dx: u16;
     unsafe {
         asm!(
-            "int 0x21",
-            in("ax") 0x2500u16 | al_vec_num as u16,
-            in("edx") edx_int_handler,
+            "int 0x10",
+            in("ax") 0x0300u16,
+            in("bx") (bh_video_page as u16) << 8,
+            lateout("ax") _,
+            lateout("cx") cx,
+            lateout("dx") dx,
+        );
+    }
+    Cursor {
+        ch_start_line: (cx >> 8) as u8,
+        cl_end_line: cx as u8,
+        dh_row: (dx >> 8) as u8,
+        dl_column: dx as u8,
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ah_06h_scroll_up(
+    al_lines: u8,
+    bh_attr: Attr,
+    ch_top: u8,
+    cl_left: u8,
+    dh_bottom: u8,
+    dl_right: u8
+) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ah_06h_scroll_up(
+    al_lines: u8,
+    bh_attr: Attr,
+    ch_top: u8,
+    cl_left: u8,
+    dh_bottom: u8,
+    dl_right: u8
+) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x0600u16 | al_lines as u16,
+            in("bx") (bh_attr.0 as u16) << 8,
+            in("cx") ((ch_top as u16) << 8) | (cl_left as u16),
+            in("dx") ((dh_bottom as u16) << 8) | (dl_right as u16),
+        );
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ah_07h_scroll_down(
+    al_lines: u8,
+    bh_attr: Attr,
+    ch_top: u8,
+    cl_left: u8,
+    dh_bottom: u8,
+    dl_right: u8
+) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ah_07h_scroll_down(
+    al_lines: u8,
+    bh_attr: Attr,
+    ch_top: u8,
+    cl_left: u8,
+    dh_bottom: u8,
+    dl_right: u8
+) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x0700u16 | al_lines as u16,
+            in("bx") (bh_attr.0 as u16) << 8,
+            in("cx") ((ch_top as u16) << 8) | (cl_left as u16),
+            in("dx") ((dh_bottom as u16) << 8) | (dl_right as u16),
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CharAttr {
+    pub ah_attr: Attr,
+    pub al_char: u8,
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ah_08h_char_attr(bh_video_page: u8) -> CharAttr {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ah_08h_char_attr(bh_video_page: u8) -> CharAttr {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x0800u16,
+            in("bx") (bh_video_page as u16) << 8,
+            lateout("ax") ax,
+        );
+    }
+    CharAttr { ah_attr: Attr((ax >> 8) as u8), al_char: ax as u8 }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ah_09h_write_char_attr(al_char: u8, bh_video_page: u8, bl_attr: Attr, cx_count: u16) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ah_09h_write_char_attr(al_char: u8, bh_video_page: u8, bl_attr: Attr, cx_count: u16) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x0900u16 | al_char as u16,
+            in("bx") ((bh_video_page as u16) << 8) | (bl_attr.0 as u16),
+            in("cx") cx_count,
+        );
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+#[allow(unused_variables)]
+pub fn int_10h_ah_0Ah_write_char(al_char: u8, bh_video_page: u8, cx_count: u16) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_10h_ah_0Ah_write_char(al_char: u8, bh_video_page: u8, cx_count: u16) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x0A00u16 | al_char as u16,
+            in("bx") (bh_video_page as u16) << 8,
+            in("cx") cx_count,
+        );
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+#[allow(unused_variables)]
+pub fn int_10h_ah_0Eh_teletype(al_char: u8, bh_video_page: u8, bl_color: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_10h_ah_0Eh_teletype(al_char: u8, bh_video_page: u8, bl_color: u8) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x0E00u16 | al_char as u16,
+            in("bx") ((bh_video_page as u16) << 8) | (bl_color as u16),
+        );
+    }
+}
+
+pub const WRITE_STRING_UPDATE_CURSOR: u8 = 0x01;
+pub const WRITE_STRING_WITH_ATTRS: u8 = 0x02;
+
+#[allow(clippy::too_many_arguments)]
+pub fn int_10h_ah_13h_write_string(
+    al_mode: u8,
+    bh_video_page: u8,
+    bl_attr: Attr,
+    cx_len: u16,
+    dh_row: u8,
+    dl_column: u8,
+    es_bp_str: RmFarPtr
+) -> Result<(), DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x1300 | al_mode as u32,
+        ebx: ((bh_video_page as u32) << 8) | bl_attr.0 as u32,
+        ecx: cx_len as u32,
+        edx: ((dh_row as u32) << 8) | dl_column as u32,
+        es: es_bp_str.segment,
+        ebp: es_bp_str.offset as u32,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ax_1001h_set_overscan(bh_color: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ax_1001h_set_overscan(bh_color: u8) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x1001u16,
+            in("bx") (bh_color as u16) << 8,
+        );
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ax_1003h_set_blink(bl_blink: bool) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ax_1003h_set_blink(bl_blink: bool) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x1003u16,
+            in("bx") bl_blink as u16,
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BhColor {
+    pub bh_color: u8,
+}
+
+#[cfg(not(dos))]
+pub fn int_10h_ax_1008h_overscan() -> BhColor {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ax_1008h_overscan() -> BhColor {
+    let mut bx: u16;
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x1008u16,
+            lateout("bx") bx,
+        );
+    }
+    BhColor { bh_color: (bx >> 8) as u8 }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ax_1010h_set_dac_reg(bx_reg: u16, dh_red: u8, ch_green: u8, cl_blue: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ax_1010h_set_dac_reg(bx_reg: u16, dh_red: u8, ch_green: u8, cl_blue: u8) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x1010u16,
+            in("bx") bx_reg,
+            in("cx") ((ch_green as u16) << 8) | (cl_blue as u16),
+            in("dx") (dh_red as u16) << 8,
+        );
+    }
+}
+
+pub fn int_10h_ax_1012h_set_dac_block(bx_first: u16, cx_count: u16, es_dx_table: RmFarPtr) -> Result<(), DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x1012,
+        ebx: bx_first as u32,
+        ecx: cx_count as u32,
+        edx: es_dx_table.offset as u32,
+        es: es_dx_table.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)
+}
+
+#[derive(Debug, Clone)]
+pub struct DacReg {
+    pub dh_red: u8,
+    pub ch_green: u8,
+    pub cl_blue: u8,
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_10h_ax_1015h_dac_reg(bx_reg: u16) -> DacReg {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ax_1015h_dac_reg(bx_reg: u16) -> DacReg {
+    let mut cx: u16;
+    let mut dx: u16;
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x1015u16,
+            in("bx") bx_reg,
+            lateout("cx") cx,
+            lateout("dx") dx,
+        );
+    }
+    DacReg { dh_red: (dx >> 8) as u8, ch_green: (cx >> 8) as u8, cl_blue: cx as u8 }
+}
+
+pub fn int_10h_ax_1017h_dac_block(bx_first: u16, cx_count: u16, es_dx_buf: RmFarPtr) -> Result<(), DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x1017,
+        ebx: bx_first as u32,
+        ecx: cx_count as u32,
+        edx: es_dx_buf.offset as u32,
+        es: es_dx_buf.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)
+}
+
+fn int_10h_ah_11h_user_font(
+    al_subfunction: u8,
+    es_bp_table: RmFarPtr,
+    cx_count: u16,
+    dx_first: u16,
+    bl_block: u8,
+    bh_bytes_per_char: u8
+) -> Result<(), DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x1100 | al_subfunction as u32,
+        ebx: ((bh_bytes_per_char as u32) << 8) | bl_block as u32,
+        ecx: cx_count as u32,
+        edx: dx_first as u32,
+        ebp: es_bp_table.offset as u32,
+        es: es_bp_table.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)
+}
+
+pub fn int_10h_ax_1100h_load_font(
+    es_bp_table: RmFarPtr,
+    cx_count: u16,
+    dx_first: u16,
+    bl_block: u8,
+    bh_bytes_per_char: u8
+) -> Result<(), DpmiErr> {
+    int_10h_ah_11h_user_font(0x00, es_bp_table, cx_count, dx_first, bl_block, bh_bytes_per_char)
+}
+
+pub fn int_10h_ax_1110h_load_font_and_program(
+    es_bp_table: RmFarPtr,
+    cx_count: u16,
+    dx_first: u16,
+    bl_block: u8,
+    bh_bytes_per_char: u8
+) -> Result<(), DpmiErr> {
+    int_10h_ah_11h_user_font(0x10, es_bp_table, cx_count, dx_first, bl_block, bh_bytes_per_char)
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+fn int_10h_ah_11h_rom_font(al_subfunction: u8, bl_block: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+fn int_10h_ah_11h_rom_font(al_subfunction: u8, bl_block: u8) {
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x1100u16 | al_subfunction as u16,
+            in("bx") bl_block as u16,
+        );
+    }
+}
+
+pub fn int_10h_ax_1101h_load_rom_8x14(bl_block: u8) {
+    int_10h_ah_11h_rom_font(0x01, bl_block);
+}
+
+pub fn int_10h_ax_1102h_load_rom_8x8(bl_block: u8) {
+    int_10h_ah_11h_rom_font(0x02, bl_block);
+}
+
+pub fn int_10h_ax_1103h_set_block_specifier(bl_specifier: u8) {
+    int_10h_ah_11h_rom_font(0x03, bl_specifier);
+}
+
+pub fn int_10h_ax_1104h_load_rom_8x16(bl_block: u8) {
+    int_10h_ah_11h_rom_font(0x04, bl_block);
+}
+
+pub fn int_10h_ax_1111h_load_rom_8x14_and_program(bl_block: u8) {
+    int_10h_ah_11h_rom_font(0x11, bl_block);
+}
+
+pub fn int_10h_ax_1112h_load_rom_8x8_and_program(bl_block: u8) {
+    int_10h_ah_11h_rom_font(0x12, bl_block);
+}
+
+pub fn int_10h_ax_1114h_load_rom_8x16_and_program(bl_block: u8) {
+    int_10h_ah_11h_rom_font(0x14, bl_block);
+}
+
+pub const FONT_INFO_INT_1FH: u8 = 0x00;
+pub const FONT_INFO_INT_43H: u8 = 0x01;
+pub const FONT_INFO_ROM_8X14: u8 = 0x02;
+pub const FONT_INFO_ROM_8X8: u8 = 0x03;
+pub const FONT_INFO_ROM_8X8_TOP: u8 = 0x04;
+pub const FONT_INFO_ROM_9X14_ALT: u8 = 0x05;
+pub const FONT_INFO_ROM_8X16: u8 = 0x06;
+pub const FONT_INFO_ROM_9X16_ALT: u8 = 0x07;
+
+#[derive(Debug, Clone)]
+pub struct FontInfo {
+    pub es_bp_table: RmFarPtr,
+    pub cx_bytes_per_char: u16,
+    pub dl_last_row: u8,
+}
+
+pub fn int_10h_ax_1130h_font_info(bh_font: u8) -> Result<FontInfo, DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x1130,
+        ebx: (bh_font as u32) << 8,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)?;
+    Ok(FontInfo {
+        es_bp_table: RmFarPtr { segment: regs.es, offset: regs.ebp as u16 },
+        cx_bytes_per_char: regs.ecx as u16,
+        dl_last_row: regs.edx as u8,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct DisplayCodes {
+    pub bl_active_code: u8,
+    pub bh_alternate_code: u8,
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+pub fn int_10h_ax_1A00h_display_codes() -> Option<DisplayCodes> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_10h_ax_1A00h_display_codes() -> Option<DisplayCodes> {
+    let mut ax: u16;
+    let mut bx: u16;
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x1A00u16,
+            lateout("ax") ax,
+            lateout("bx") bx,
+        );
+    }
+    if ax as u8 != 0x1A { return None; }
+    Some(DisplayCodes { bl_active_code: bx as u8, bh_alternate_code: (bx >> 8) as u8 })
+}
+
+#[derive(Debug, Clone)]
+pub struct EgaInfo {
+    pub bh_mono: bool,
+    pub bl_memory_64k_minus_one: u8,
+    pub ch_feature_bits: u8,
+    pub cl_switches: u8,
+}
+
+#[cfg(not(dos))]
+pub fn int_10h_ah_12h_bl_10h_ega_info() -> Option<EgaInfo> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_10h_ah_12h_bl_10h_ega_info() -> Option<EgaInfo> {
+    let mut bx: u16;
+    let mut cx: u16;
+    unsafe {
+        asm!(
+            "int 0x10",
+            in("ax") 0x1200u16,
+            inlateout("bx") 0xFF10u16 => bx,
+            inlateout("cx") 0xFFFFu16 => cx,
+        );
+    }
+    // Without EGA services BL comes back unchanged.
+    if bx as u8 == 0x10 { return None; }
+    Some(EgaInfo {
+        bh_mono: (bx >> 8) as u8 != 0,
+        bl_memory_64k_minus_one: bx as u8,
+        ch_feature_bits: (cx >> 8) as u8,
+        cl_switches: cx as u8,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct AlSupported {
+    pub al_supported: bool,
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ah_1Bh_state_info(es_di_buf: RmFarPtr) -> Result<AlSupported, DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x1B00,
+        ebx: 0x0000,
+        edi: es_di_buf.offset as u32,
+        es: es_di_buf.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)?;
+    Ok(AlSupported { al_supported: regs.eax as u8 == 0x1B })
+}
+
+pub const VIDEO_STATE_HARDWARE: u16 = 0x0001;
+pub const VIDEO_STATE_BIOS_DATA: u16 = 0x0002;
+pub const VIDEO_STATE_DAC: u16 = 0x0004;
+
+#[derive(Debug, Clone)]
+pub struct BxBlocks {
+    pub bx_blocks: u16,
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_1C00h_state_size(cx_states: u16) -> Result<Option<BxBlocks>, DpmiErr> {
+    let mut regs = RmRegs { eax: 0x1C00, ecx: cx_states as u32, ..RmRegs::default() };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)?;
+    if regs.eax as u8 != 0x1C { return Ok(None); }
+    Ok(Some(BxBlocks { bx_blocks: regs.ebx as u16 }))
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_1C01h_save_state(cx_states: u16, es_bx_buf: RmFarPtr) -> Result<(), DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x1C01,
+        ebx: es_bx_buf.offset as u32,
+        ecx: cx_states as u32,
+        es: es_bx_buf.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_1C02h_restore_state(cx_states: u16, es_bx_buf: RmFarPtr) -> Result<(), DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x1C02,
+        ebx: es_bx_buf.offset as u32,
+        ecx: cx_states as u32,
+        es: es_bx_buf.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x10, &mut regs)
+}
+
+pub const VBE_SUPPORTED: u8 = 0x4F;
+
+#[derive(Debug, Clone)]
+pub enum VbeErr {
+    Dpmi(DpmiErr),
+    Status(AxErr),
+    /// The controller info block does not start with "VESA".
+    BadSignature([u8; 4]),
+}
+
+impl From<DpmiErr> for VbeErr {
+    fn from(e: DpmiErr) -> VbeErr { VbeErr::Dpmi(e) }
+}
+
+#[allow(non_snake_case)]
+fn int_10h_ah_4Fh(regs: &mut RmRegs) -> Result<(), VbeErr> {
+    int_31h_ax_0300h_simulate_rm_int(0x10, regs)?;
+    let ax = regs.eax as u16;
+    if ax == VBE_SUPPORTED as u16 {
+        Ok(())
+    } else {
+        Err(VbeErr::Status(AxErr { ax_err: ax }))
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F00h_controller_info(es_di_buf: RmFarPtr) -> Result<(), VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F00,
+        edi: es_di_buf.offset as u32,
+        es: es_di_buf.segment,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F01h_mode_info(cx_mode: u16, es_di_buf: RmFarPtr) -> Result<(), VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F01,
+        ecx: cx_mode as u32,
+        edi: es_di_buf.offset as u32,
+        es: es_di_buf.segment,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)
+}
+
+pub const VBE_MODE_LFB: u16 = 0x4000;
+pub const VBE_MODE_NO_CLEAR: u16 = 0x8000;
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F02h_set_mode(bx_mode: u16) -> Result<(), VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F02,
+        ebx: bx_mode as u32,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)
+}
+
+#[derive(Debug, Clone)]
+pub struct BxMode {
+    pub bx_mode: u16,
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F03h_mode() -> Result<BxMode, VbeErr> {
+    let mut regs = RmRegs { eax: 0x4F03, ..RmRegs::default() };
+    int_10h_ah_4Fh(&mut regs)?;
+    Ok(BxMode { bx_mode: regs.ebx as u16 })
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F05h_bh_00h_set_window(bl_window: u8, dx_position: u16) -> Result<(), VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F05,
+        ebx: bl_window as u32,
+        edx: dx_position as u32,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)
+}
+
+#[derive(Debug, Clone)]
+pub struct DxPosition {
+    pub dx_position: u16,
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F05h_bh_01h_window(bl_window: u8) -> Result<DxPosition, VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F05,
+        ebx: 0x0100 | bl_window as u32,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)?;
+    Ok(DxPosition { dx_position: regs.edx as u16 })
+}
+
+#[derive(Debug, Clone)]
+pub struct LogicalWidth {
+    pub bx_bytes_per_line: u16,
+    pub cx_pixels_per_line: u16,
+    pub dx_lines: u16,
+}
+
+#[allow(non_snake_case)]
+fn int_10h_ax_4F06h(bl_subfunction: u8, cx_width: u16) -> Result<LogicalWidth, VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F06,
+        ebx: bl_subfunction as u32,
+        ecx: cx_width as u32,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)?;
+    Ok(LogicalWidth {
+        bx_bytes_per_line: regs.ebx as u16,
+        cx_pixels_per_line: regs.ecx as u16,
+        dx_lines: regs.edx as u16,
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F06h_bl_00h_set_width_pixels(cx_pixels: u16) -> Result<LogicalWidth, VbeErr> {
+    int_10h_ax_4F06h(0x00, cx_pixels)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F06h_bl_01h_width() -> Result<LogicalWidth, VbeErr> {
+    int_10h_ax_4F06h(0x01, 0)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F06h_bl_02h_set_width_bytes(cx_bytes: u16) -> Result<LogicalWidth, VbeErr> {
+    int_10h_ax_4F06h(0x02, cx_bytes)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F06h_bl_03h_max_width() -> Result<LogicalWidth, VbeErr> {
+    int_10h_ax_4F06h(0x03, 0)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F07h_bl_00h_set_display_start(cx_x: u16, dx_y: u16) -> Result<(), VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F07,
+        ebx: 0x00,
+        ecx: cx_x as u32,
+        edx: dx_y as u32,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F07h_bl_80h_set_display_start_in_vretrace(cx_x: u16, dx_y: u16) -> Result<(), VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F07,
+        ebx: 0x80,
+        ecx: cx_x as u32,
+        edx: dx_y as u32,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)
+}
+
+#[derive(Debug, Clone)]
+pub struct DisplayStart {
+    pub cx_x: u16,
+    pub dx_y: u16,
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F07h_bl_01h_display_start() -> Result<DisplayStart, VbeErr> {
+    let mut regs = RmRegs { eax: 0x4F07, ebx: 0x01, ..RmRegs::default() };
+    int_10h_ah_4Fh(&mut regs)?;
+    Ok(DisplayStart { cx_x: regs.ecx as u16, dx_y: regs.edx as u16 })
+}
+
+#[allow(non_snake_case)]
+fn int_10h_ax_4F09h(bl_subfunction: u8, cx_count: u16, dx_first: u16, es_di_table: RmFarPtr) -> Result<(), VbeErr> {
+    let mut regs = RmRegs {
+        eax: 0x4F09,
+        ebx: bl_subfunction as u32,
+        ecx: cx_count as u32,
+        edx: dx_first as u32,
+        edi: es_di_table.offset as u32,
+        es: es_di_table.segment,
+        ..RmRegs::default()
+    };
+    int_10h_ah_4Fh(&mut regs)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F09h_bl_00h_set_palette(cx_count: u16, dx_first: u16, es_di_table: RmFarPtr) -> Result<(), VbeErr> {
+    int_10h_ax_4F09h(0x00, cx_count, dx_first, es_di_table)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F09h_bl_01h_palette(cx_count: u16, dx_first: u16, es_di_buf: RmFarPtr) -> Result<(), VbeErr> {
+    int_10h_ax_4F09h(0x01, cx_count, dx_first, es_di_buf)
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F09h_bl_80h_set_palette_in_vretrace(
+    cx_count: u16,
+    dx_first: u16,
+    es_di_table: RmFarPtr
+) -> Result<(), VbeErr> {
+    int_10h_ax_4F09h(0x80, cx_count, dx_first, es_di_table)
+}
+
+#[derive(Debug, Clone)]
+pub struct PmInterfaceTable {
+    pub es_di_table: RmFarPtr,
+    pub cx_len: u16,
+}
+
+#[allow(non_snake_case)]
+pub fn int_10h_ax_4F0Ah_bl_00h_pm_interface() -> Result<PmInterfaceTable, VbeErr> {
+    let mut regs = RmRegs { eax: 0x4F0A, ..RmRegs::default() };
+    int_10h_ah_4Fh(&mut regs)?;
+    Ok(PmInterfaceTable {
+        es_di_table: RmFarPtr { segment: regs.es, offset: regs.edi as u16 },
+        cx_len: regs.ecx as u16,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct AxKey {
+    pub ah_scan_code: u8,
+    pub al_ascii: u8,
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+fn int_16h_read_key(ah_func: u8) -> AxKey {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+fn int_16h_read_key(ah_func: u8) -> AxKey {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x16",
+            inlateout("ax") (ah_func as u16) << 8 => ax,
+        );
+    }
+    AxKey { ah_scan_code: (ax >> 8) as u8, al_ascii: ax as u8 }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+fn int_16h_peek_key(ah_func: u8) -> Result<Option<AxKey>, DpmiErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+fn int_16h_peek_key(ah_func: u8) -> Result<Option<AxKey>, DpmiErr> {
+    let mut regs = RmRegs { eax: (ah_func as u32) << 8, ..RmRegs::default() };
+    int_31h_ax_0300h_simulate_rm_int(0x16, &mut regs)?;
+    if regs.flags & u16::from(ZF) == 0 {
+        Ok(Some(AxKey { ah_scan_code: (regs.eax >> 8) as u8, al_ascii: regs.eax as u8 }))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn int_16h_ah_00h_read_key() -> AxKey {
+    int_16h_read_key(0x00)
+}
+
+pub fn int_16h_ah_01h_peek_key() -> Result<Option<AxKey>, DpmiErr> {
+    int_16h_peek_key(0x01)
+}
+
+pub const SHIFT_RIGHT_SHIFT: u16 = 0x0001;
+pub const SHIFT_LEFT_SHIFT: u16 = 0x0002;
+pub const SHIFT_CTRL: u16 = 0x0004;
+pub const SHIFT_ALT: u16 = 0x0008;
+pub const SHIFT_SCROLL_LOCK_ON: u16 = 0x0010;
+pub const SHIFT_NUM_LOCK_ON: u16 = 0x0020;
+pub const SHIFT_CAPS_LOCK_ON: u16 = 0x0040;
+pub const SHIFT_INSERT_ON: u16 = 0x0080;
+pub const SHIFT_LEFT_CTRL: u16 = 0x0100;
+pub const SHIFT_LEFT_ALT: u16 = 0x0200;
+pub const SHIFT_RIGHT_CTRL: u16 = 0x0400;
+pub const SHIFT_RIGHT_ALT: u16 = 0x0800;
+pub const SHIFT_SCROLL_LOCK: u16 = 0x1000;
+pub const SHIFT_NUM_LOCK: u16 = 0x2000;
+pub const SHIFT_CAPS_LOCK: u16 = 0x4000;
+pub const SHIFT_SYS_REQ: u16 = 0x8000;
+
+#[derive(Debug, Clone)]
+pub struct AlShiftFlags {
+    pub al_shift_flags: u8,
+}
+
+#[cfg(not(dos))]
+pub fn int_16h_ah_02h_shift_flags() -> AlShiftFlags {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_16h_ah_02h_shift_flags() -> AlShiftFlags {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x16",
+            inlateout("ax") 0x0200u16 => ax,
+        );
+    }
+    AlShiftFlags { al_shift_flags: ax as u8 }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_16h_ax_0305h_set_typematic(bh_delay: u8, bl_rate: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_16h_ax_0305h_set_typematic(bh_delay: u8, bl_rate: u8) {
+    unsafe {
+        asm!(
+            "int 0x16",
+            in("ax") 0x0305u16,
+            in("bx") ((bh_delay as u16) << 8) | bl_rate as u16,
+        );
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_16h_ah_05h_stuff_key(ch_scan_code: u8, cl_ascii: u8) -> Result<(), AlErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_16h_ah_05h_stuff_key(ch_scan_code: u8, cl_ascii: u8) -> Result<(), AlErr> {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x16",
+            inlateout("ax") 0x0500u16 => ax,
+            in("cx") ((ch_scan_code as u16) << 8) | cl_ascii as u16,
+        );
+    }
+    NonZeroU8::new(ax as u8).map(|al_err| AlErr { al_err }).map_or(Ok(()), Err)
+}
+
+pub fn int_16h_ah_10h_read_key_ext() -> AxKey {
+    int_16h_read_key(0x10)
+}
+
+pub fn int_16h_ah_11h_peek_key_ext() -> Result<Option<AxKey>, DpmiErr> {
+    int_16h_peek_key(0x11)
+}
+
+#[derive(Debug, Clone)]
+pub struct AxShiftFlags {
+    pub ax_shift_flags: u16,
+}
+
+#[cfg(not(dos))]
+pub fn int_16h_ah_12h_shift_flags_ext() -> AxShiftFlags {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_16h_ah_12h_shift_flags_ext() -> AxShiftFlags {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x16",
+            inlateout("ax") 0x1200u16 => ax,
+        );
+    }
+    AxShiftFlags { ax_shift_flags: ax }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+fn int_33h(ax_func: u16, bx: u16, cx: u16, dx: u16) -> [u16; 4] {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+fn int_33h(ax_func: u16, bx: u16, cx: u16, dx: u16) -> [u16; 4] {
+    let mut ax: u16;
+    let mut bx = bx;
+    let mut cx = cx;
+    let mut dx = dx;
+    unsafe {
+        asm!(
+            "int 0x33",
+            inlateout("ax") ax_func => ax,
+            inlateout("bx") bx,
+            inlateout("cx") cx,
+            inlateout("dx") dx,
+        );
+    }
+    [ax, bx, cx, dx]
+}
+
+pub const MOUSE_PRESENT: u16 = 0xFFFF;
+
+#[derive(Debug, Clone)]
+pub struct BxButtons {
+    pub bx_buttons: u16,
+}
+
+/// Resets the mouse driver, or returns `None` when no driver is installed.
+/// Drivers reporting FFFFh buttons are read as two; zero means neither two nor three.
+pub fn int_33h_ax_0000h_reset() -> Option<BxButtons> {
+    let [ax, bx, _, _] = int_33h(0x0000, 0, 0, 0);
+    if ax == MOUSE_PRESENT {
+        Some(BxButtons { bx_buttons: if bx == 0xFFFF { 2 } else { bx } })
+    } else {
+        None
+    }
+}
+
+pub fn int_33h_ax_0001h_show_cursor() {
+    int_33h(0x0001, 0, 0, 0);
+}
+
+pub fn int_33h_ax_0002h_hide_cursor() {
+    int_33h(0x0002, 0, 0, 0);
+}
+
+pub const MOUSE_BUTTON_LEFT: u16 = 0x0001;
+pub const MOUSE_BUTTON_RIGHT: u16 = 0x0002;
+pub const MOUSE_BUTTON_MIDDLE: u16 = 0x0004;
+
+#[derive(Debug, Clone)]
+pub struct MouseState {
+    pub bx_buttons: u16,
+    pub cx_x: u16,
+    pub dx_y: u16,
+}
+
+pub fn int_33h_ax_0003h_state() -> MouseState {
+    let [_, bx, cx, dx] = int_33h(0x0003, 0, 0, 0);
+    MouseState { bx_buttons: bx, cx_x: cx, dx_y: dx }
+}
+
+pub fn int_33h_ax_0004h_set_position(cx_x: u16, dx_y: u16) {
+    int_33h(0x0004, 0, cx_x, dx_y);
+}
+
+#[derive(Debug, Clone)]
+pub struct ButtonInfo {
+    pub ax_buttons: u16,
+    pub bx_count: u16,
+    pub cx_x: u16,
+    pub dx_y: u16,
+}
+
+/// `bx_count` counts presses since the last call, `cx_x` and `dx_y` are where the last one happened.
+pub fn int_33h_ax_0005h_press_info(bx_button: u16) -> ButtonInfo {
+    let [ax, bx, cx, dx] = int_33h(0x0005, bx_button, 0, 0);
+    ButtonInfo { ax_buttons: ax, bx_count: bx, cx_x: cx, dx_y: dx }
+}
+
+pub fn int_33h_ax_0006h_release_info(bx_button: u16) -> ButtonInfo {
+    let [ax, bx, cx, dx] = int_33h(0x0006, bx_button, 0, 0);
+    ButtonInfo { ax_buttons: ax, bx_count: bx, cx_x: cx, dx_y: dx }
+}
+
+pub fn int_33h_ax_0007h_set_x_range(cx_min: u16, dx_max: u16) {
+    int_33h(0x0007, 0, cx_min, dx_max);
+}
+
+pub fn int_33h_ax_0008h_set_y_range(cx_min: u16, dx_max: u16) {
+    int_33h(0x0008, 0, cx_min, dx_max);
+}
+
+/// `es_dx_masks` holds 16 words of screen mask followed by 16 words of cursor mask.
+pub fn int_33h_ax_0009h_set_graphics_cursor(bx_hot_x: i16, cx_hot_y: i16, es_dx_masks: RmFarPtr) -> Result<(), DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x0009,
+        ebx: bx_hot_x as u16 as u32,
+        ecx: cx_hot_y as u16 as u32,
+        edx: es_dx_masks.offset as u32,
+        es: es_dx_masks.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x33, &mut regs)
+}
+
+pub const MOUSE_TEXT_CURSOR_SOFTWARE: u16 = 0;
+pub const MOUSE_TEXT_CURSOR_HARDWARE: u16 = 1;
+
+/// With a software cursor `cx` and `dx` are the AND and XOR masks applied to the character
+/// and attribute; with a hardware cursor they are the start and end scan lines.
+#[allow(non_snake_case)]
+pub fn int_33h_ax_000Ah_set_text_cursor(bx_type: u16, cx_screen_mask: u16, dx_cursor_mask: u16) {
+    int_33h(0x000A, bx_type, cx_screen_mask, dx_cursor_mask);
+}
+
+#[derive(Debug, Clone)]
+pub struct Mickeys {
+    pub cx_x: i16,
+    pub dx_y: i16,
+}
+
+/// Motion since the last call, in mickeys.
+#[allow(non_snake_case)]
+pub fn int_33h_ax_000Bh_motion() -> Mickeys {
+    let [_, _, cx, dx] = int_33h(0x000B, 0, 0, 0);
+    Mickeys { cx_x: cx as i16, dx_y: dx as i16 }
+}
+
+pub const MOUSE_EVENT_MOVE: u16 = 0x0001;
+pub const MOUSE_EVENT_LEFT_DOWN: u16 = 0x0002;
+pub const MOUSE_EVENT_LEFT_UP: u16 = 0x0004;
+pub const MOUSE_EVENT_RIGHT_DOWN: u16 = 0x0008;
+pub const MOUSE_EVENT_RIGHT_UP: u16 = 0x0010;
+pub const MOUSE_EVENT_MIDDLE_DOWN: u16 = 0x0020;
+pub const MOUSE_EVENT_MIDDLE_UP: u16 = 0x0040;
+pub const MOUSE_EVENT_ALL: u16 = 0x007F;
+
+/// The handler is a real-mode far procedure, called by the driver with the event mask in AX,
+/// buttons in BX, position in CX and DX, and raw mickeys in SI and DI.
+#[allow(non_snake_case)]
+pub fn int_33h_ax_000Ch_set_handler(cx_mask: u16, es_dx_handler: RmFarPtr) -> Result<(), DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x000C,
+        ecx: cx_mask as u32,
+        edx: es_dx_handler.offset as u32,
+        es: es_dx_handler.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x33, &mut regs)
+}
+
+/// Sets how many mickeys move the cursor 8 pixels.
+#[allow(non_snake_case)]
+pub fn int_33h_ax_000Fh_set_mickey_ratio(cx_x: u16, dx_y: u16) {
+    int_33h(0x000F, 0, cx_x, dx_y);
+}
+
+#[derive(Debug, Clone)]
+pub struct MouseHandler {
+    pub cx_mask: u16,
+    pub es_dx_handler: RmFarPtr,
+}
+
+/// Installs a handler like 000Ch and returns the one it replaced.
+pub fn int_33h_ax_0014h_swap_handler(cx_mask: u16, es_dx_handler: RmFarPtr) -> Result<MouseHandler, DpmiErr> {
+    let mut regs = RmRegs {
+        eax: 0x0014,
+        ecx: cx_mask as u32,
+        edx: es_dx_handler.offset as u32,
+        es: es_dx_handler.segment,
+        ..RmRegs::default()
+    };
+    int_31h_ax_0300h_simulate_rm_int(0x33, &mut regs)?;
+    Ok(MouseHandler {
+        cx_mask: regs.ecx as u16,
+        es_dx_handler: RmFarPtr { segment: regs.es, offset: regs.edx as u16 },
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct TickCount {
+    pub cx_dx_ticks: u32,
+    /// Nonzero if midnight passed since the last read; reading clears it.
+    pub al_midnight: u8,
+}
+
+/// Reads and clears the midnight flag DOS relies on to advance its date; prefer
+/// [`rtc::ticks`] under DOS.
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+pub fn int_1Ah_ah_00h_ticks() -> TickCount {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// Reads and clears the midnight flag DOS relies on to advance its date; prefer
+/// [`rtc::ticks`] under DOS.
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_1Ah_ah_00h_ticks() -> TickCount {
+    let mut ax: u16;
+    let mut cx: u16;
+    let mut dx: u16;
+    unsafe {
+        asm!(
+            "int 0x1A",
+            inlateout("ax") 0x0000u16 => ax,
+            lateout("cx") cx,
+            lateout("dx") dx,
+        );
+    }
+    TickCount { cx_dx_ticks: ((cx as u32) << 16) | dx as u32, al_midnight: ax as u8 }
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+#[allow(unused_variables)]
+pub fn int_1Ah_ah_01h_set_ticks(cx_dx_ticks: u32) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_1Ah_ah_01h_set_ticks(cx_dx_ticks: u32) {
+    unsafe {
+        asm!(
+            "int 0x1A",
+            in("ax") 0x0100u16,
+            in("cx") (cx_dx_ticks >> 16) as u16,
+            in("dx") cx_dx_ticks as u16,
+        );
+    }
+}
+
+/// Calls an RTC function, returning CX and DX, or `None` when the BIOS sets CF.
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+#[allow(unused_variables)]
+fn int_1Ah_rtc(ah_func: u8, cx: u16, dx: u16) -> Option<[u16; 2]> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+fn int_1Ah_rtc(ah_func: u8, cx: u16, dx: u16) -> Option<[u16; 2]> {
+    let mut flags: u16;
+    let mut cx = cx;
+    let mut dx = dx;
+    unsafe {
+        asm!(
+            "clc",
+            "int 0x1A",
+            "lahf",
+            inlateout("ax") (ah_func as u16) << 8 => flags,
+            inlateout("cx") cx,
+            inlateout("dx") dx,
+        );
+    }
+    if ((flags >> 8) as u8) & CF == 0 {
+        Some([cx, dx])
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RtcTime {
+    pub ch_hours_bcd: u8,
+    pub cl_minutes_bcd: u8,
+    pub dh_seconds_bcd: u8,
+    pub dl_dst: u8,
+}
+
+/// Returns `None` if the clock is not running.
+#[allow(non_snake_case)]
+pub fn int_1Ah_ah_02h_rtc_time() -> Option<RtcTime> {
+    let [cx, dx] = int_1Ah_rtc(0x02, 0, 0)?;
+    Some(RtcTime {
+        ch_hours_bcd: (cx >> 8) as u8,
+        cl_minutes_bcd: cx as u8,
+        dh_seconds_bcd: (dx >> 8) as u8,
+        dl_dst: dx as u8,
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn int_1Ah_ah_03h_set_rtc_time(time: &RtcTime) {
+    int_1Ah_rtc(
+        0x03,
+        ((time.ch_hours_bcd as u16) << 8) | time.cl_minutes_bcd as u16,
+        ((time.dh_seconds_bcd as u16) << 8) | time.dl_dst as u16,
+    );
+}
+
+#[derive(Debug, Clone)]
+pub struct RtcDate {
+    pub ch_century_bcd: u8,
+    pub cl_year_bcd: u8,
+    pub dh_month_bcd: u8,
+    pub dl_day_bcd: u8,
+}
+
+/// Returns `None` if the clock is not running.
+#[allow(non_snake_case)]
+pub fn int_1Ah_ah_04h_rtc_date() -> Option<RtcDate> {
+    let [cx, dx] = int_1Ah_rtc(0x04, 0, 0)?;
+    Some(RtcDate {
+        ch_century_bcd: (cx >> 8) as u8,
+        cl_year_bcd: cx as u8,
+        dh_month_bcd: (dx >> 8) as u8,
+        dl_day_bcd: dx as u8,
+    })
+}
+
+#[allow(non_snake_case)]
+pub fn int_1Ah_ah_05h_set_rtc_date(date: &RtcDate) {
+    int_1Ah_rtc(
+        0x05,
+        ((date.ch_century_bcd as u16) << 8) | date.cl_year_bcd as u16,
+        ((date.dh_month_bcd as u16) << 8) | date.dl_day_bcd as u16,
+    );
+}
+
+#[derive(Debug, Clone)]
+pub struct AlarmErr;
+
+/// Makes the RTC raise INT 4Ah at the given time of day. Fails if an alarm is already
+/// set or the clock is not running.
+#[allow(non_snake_case)]
+pub fn int_1Ah_ah_06h_set_alarm(ch_hours_bcd: u8, cl_minutes_bcd: u8, dh_seconds_bcd: u8) -> Result<(), AlarmErr> {
+    let cx = ((ch_hours_bcd as u16) << 8) | cl_minutes_bcd as u16;
+    int_1Ah_rtc(0x06, cx, (dh_seconds_bcd as u16) << 8).map(|_| ()).ok_or(AlarmErr)
+}
+
+#[allow(non_snake_case)]
+pub fn int_1Ah_ah_07h_cancel_alarm() {
+    int_1Ah_rtc(0x07, 0, 0);
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemDate {
+    pub cx_year: u16,
+    pub dh_month: u8,
+    pub dl_day: u8,
+    /// 0 for Sunday.
+    pub al_weekday: u8,
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+pub fn int_21h_ah_2Ah_date() -> SystemDate {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_21h_ah_2Ah_date() -> SystemDate {
+    let mut ax: u16;
+    let mut cx: u16;
+    let mut dx: u16;
+    unsafe {
+        asm!(
+            "int 0x21",
+            inlateout("ax") 0x2A00u16 => ax,
+            lateout("cx") cx,
+            lateout("dx") dx,
+        );
+    }
+    SystemDate { cx_year: cx, dh_month: (dx >> 8) as u8, dl_day: dx as u8, al_weekday: ax as u8 }
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+#[allow(unused_variables)]
+pub fn int_21h_ah_2Bh_set_date(cx_year: u16, dh_month: u8, dl_day: u8) -> Result<(), AlErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_21h_ah_2Bh_set_date(cx_year: u16, dh_month: u8, dl_day: u8) -> Result<(), AlErr> {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x21",
+            inlateout("ax") 0x2B00u16 => ax,
+            in("cx") cx_year,
+            in("dx") ((dh_month as u16) << 8) | dl_day as u16,
+        );
+    }
+    NonZeroU8::new(ax as u8).map(|al_err| AlErr { al_err }).map_or(Ok(()), Err)
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemTime {
+    pub ch_hours: u8,
+    pub cl_minutes: u8,
+    pub dh_seconds: u8,
+    pub dl_hundredths: u8,
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+pub fn int_21h_ah_2Ch_time() -> SystemTime {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_21h_ah_2Ch_time() -> SystemTime {
+    let mut cx: u16;
+    let mut dx: u16;
+    unsafe {
+        asm!(
+            "int 0x21",
+            inlateout("ax") 0x2C00u16 => _,
+            lateout("cx") cx,
+            lateout("dx") dx,
+        );
+    }
+    SystemTime {
+        ch_hours: (cx >> 8) as u8,
+        cl_minutes: cx as u8,
+        dh_seconds: (dx >> 8) as u8,
+        dl_hundredths: dx as u8,
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(non_snake_case)]
+#[allow(unused_variables)]
+pub fn int_21h_ah_2Dh_set_time(ch_hours: u8, cl_minutes: u8, dh_seconds: u8, dl_hundredths: u8) -> Result<(), AlErr> {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[allow(non_snake_case)]
+#[inline]
+pub fn int_21h_ah_2Dh_set_time(ch_hours: u8, cl_minutes: u8, dh_seconds: u8, dl_hundredths: u8) -> Result<(), AlErr> {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "int 0x21",
+            inlateout("ax") 0x2D00u16 => ax,
+            in("cx") ((ch_hours as u16) << 8) | cl_minutes as u16,
+            in("dx") ((dh_seconds as u16) << 8) | dl_hundredths as u16,
+        );
+    }
+    NonZeroU8::new(ax as u8).map(|al_err| AlErr { al_err }).map_or(Ok(()), Err)
+}
+
+#[derive(Debug, Clone)]
+pub struct BxCountry {
+    pub bx_country: u16,
+}
+
+pub struct IntHandler {
+    pub es_ebx_int_handler: PmFarPtr,
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_21h_ah_35h_get_int(al_vec_num: u8) -> IntHandler {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_21h_ah_35h_get_int(al_vec_num: u8) -> IntHandler {
+    let mut ebx_offset: u32;
+    let mut es_selector: u16;
+    unsafe {
+        asm!(
+            "push es",
+            "int 0x21",
+            "mov {es_selector:x}, es",
+            "pop es",
+            es_selector = lateout(reg) es_selector,
+            in("ax") 0x3500u16 | al_vec_num as u16,
+            lateout("ebx") ebx_offset,
+        );
+    }
+    IntHandler { es_ebx_int_handler: PmFarPtr { offset: ebx_offset, selector: es_selector } }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub fn int_21h_ah_25h_set_int(al_vec_num: u8, ds_edx_int_handler: PmFarPtr) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+pub fn int_21h_ah_25h_set_int(al_vec_num: u8, ds_edx_int_handler: PmFarPtr) {
+    unsafe {
+        asm!(
+            "push ds",
+            "mov ds, {ds_selector:x}",
+            "int 0x21",
+            "pop ds",
+            ds_selector = in(reg) ds_edx_int_handler.selector,
+            in("ax") 0x2500u16 | al_vec_num as u16,
+            in("edx") ds_edx_int_handler.offset,
+        );
+    }
+}
+
+/// # Safety
+///
+/// Reading some ports changes device state, such as the VGA attribute flip-flop or a latched
+/// PIT count; the read must not disturb a driver using the device.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn port_in_u8(dx_port: u16) -> u8 {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// Reading some ports changes device state, such as the VGA attribute flip-flop or a latched
+/// PIT count; the read must not disturb a driver using the device.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn port_in_u8(dx_port: u16) -> u8 {
+    let mut al: u8;
+    unsafe {
+        asm!(
+            "in al, dx",
+            in("dx") dx_port,
+            lateout("al") al,
+            options(nomem, nostack, preserves_flags),
+        );
+    }
+    al
+}
+
+/// # Safety
+///
+/// Reading some ports changes device state, such as the VGA attribute flip-flop or a latched
+/// PIT count; the read must not disturb a driver using the device.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn port_in_u16(dx_port: u16) -> u16 {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// Reading some ports changes device state, such as the VGA attribute flip-flop or a latched
+/// PIT count; the read must not disturb a driver using the device.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn port_in_u16(dx_port: u16) -> u16 {
+    let mut ax: u16;
+    unsafe {
+        asm!(
+            "in ax, dx",
+            in("dx") dx_port,
+            lateout("ax") ax,
+            options(nomem, nostack, preserves_flags),
+        );
+    }
+    ax
+}
+
+/// # Safety
+///
+/// The write must keep the invariants of drivers using the device, such as the PIC, PIT or
+/// DMA controller state this crate's interrupt handlers rely on.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn port_out_u8(dx_port: u16, al_value: u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// The write must keep the invariants of drivers using the device, such as the PIC, PIT or
+/// DMA controller state this crate's interrupt handlers rely on.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn port_out_u8(dx_port: u16, al_value: u8) {
+    unsafe {
+        asm!(
+            "out dx, al",
+            in("dx") dx_port,
+            in("al") al_value,
+            options(nomem, nostack, preserves_flags),
+        );
+    }
+}
+
+/// # Safety
+///
+/// The write must keep the invariants of drivers using the device, such as the PIC, PIT or
+/// DMA controller state this crate's interrupt handlers rely on.
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+pub unsafe fn port_out_u16(dx_port: u16, ax_value: u16) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// # Safety
+///
+/// The write must keep the invariants of drivers using the device, such as the PIC, PIT or
+/// DMA controller state this crate's interrupt handlers rely on.
+#[cfg(dos)]
+#[inline]
+pub unsafe fn port_out_u16(dx_port: u16, ax_value: u16) {
+    unsafe {
+        asm!(
+            "out dx, ax",
+            in("dx") dx_port,
+            in("ax") ax_value,
+            options(nomem, nostack, preserves_flags),
         );
     }
 }
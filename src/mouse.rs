@@ -0,0 +1,289 @@
+#[cfg(dos)]
+use core::arch::naked_asm;
+use core::cell::UnsafeCell;
+#[cfg(dos)]
+use core::mem::offset_of;
+use core::mem::transmute;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU16, Ordering};
+use crate::*;
+use crate::isr::*;
+use crate::xfer::XferBuf;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TextCursor {
+    /// The character cell under the cursor is ANDed with `screen_mask` and XORed with `cursor_mask`.
+    Software { screen_mask: u16, cursor_mask: u16 },
+    Hardware { start_line: u16, end_line: u16 },
+}
+
+/// An event reported through INT 33h function 000Ch.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct MouseEvent {
+    /// `MOUSE_EVENT_*` flags for what triggered the call.
+    pub events: u16,
+    /// `MOUSE_BUTTON_*` flags for the buttons held down.
+    pub buttons: u16,
+    pub x: u16,
+    pub y: u16,
+    pub mickeys_x: i16,
+    pub mickeys_y: i16,
+}
+
+/// A detected mouse driver; the API is only reachable through this, after a reset.
+#[derive(Debug)]
+pub struct Mouse {
+    buttons: u16,
+}
+
+impl Mouse {
+    /// Resets the driver, hiding the cursor and centering it, or returns `None` without one.
+    pub fn detect() -> Option<Mouse> {
+        int_33h_ax_0000h_reset().map(|r| Mouse { buttons: r.bx_buttons })
+    }
+
+    pub fn buttons(&self) -> u16 { self.buttons }
+
+    /// The driver keeps a counter: each `hide_cursor` needs its own `show_cursor`.
+    pub fn show_cursor(&self) {
+        int_33h_ax_0001h_show_cursor();
+    }
+
+    pub fn hide_cursor(&self) {
+        int_33h_ax_0002h_hide_cursor();
+    }
+
+    pub fn state(&self) -> MouseState {
+        int_33h_ax_0003h_state()
+    }
+
+    pub fn set_position(&self, x: u16, y: u16) {
+        int_33h_ax_0004h_set_position(x, y);
+    }
+
+    /// `button` is 0 for left, 1 for right and 2 for middle.
+    pub fn press_info(&self, button: u16) -> ButtonInfo {
+        int_33h_ax_0005h_press_info(button)
+    }
+
+    pub fn release_info(&self, button: u16) -> ButtonInfo {
+        int_33h_ax_0006h_release_info(button)
+    }
+
+    pub fn set_range(&self, x_min: u16, x_max: u16, y_min: u16, y_max: u16) {
+        int_33h_ax_0007h_set_x_range(x_min, x_max);
+        int_33h_ax_0008h_set_y_range(y_min, y_max);
+    }
+
+    /// Copies the masks into `xfer`, which needs 64 bytes; the driver copies them on its side.
+    pub fn set_graphics_cursor(
+        &self,
+        xfer: &XferBuf,
+        hot_x: i16,
+        hot_y: i16,
+        screen_mask: &[u16; 16],
+        cursor_mask: &[u16; 16],
+    ) -> Result<(), DpmiErr> {
+        for (i, &word) in screen_mask.iter().chain(cursor_mask.iter()).enumerate() {
+            xfer.copy_in((i * 2) as u16, &word.to_le_bytes());
+        }
+        int_33h_ax_0009h_set_graphics_cursor(hot_x, hot_y, xfer.ptr())
+    }
+
+    pub fn set_text_cursor(&self, cursor: TextCursor) {
+        match cursor {
+            TextCursor::Software { screen_mask, cursor_mask } =>
+                int_33h_ax_000Ah_set_text_cursor(MOUSE_TEXT_CURSOR_SOFTWARE, screen_mask, cursor_mask),
+            TextCursor::Hardware { start_line, end_line } =>
+                int_33h_ax_000Ah_set_text_cursor(MOUSE_TEXT_CURSOR_HARDWARE, start_line, end_line),
+        }
+    }
+
+    pub fn motion(&self) -> Mickeys {
+        int_33h_ax_000Bh_motion()
+    }
+
+    /// Sets the mickeys per 8 pixels, 8 horizontally and 16 vertically after a reset.
+    pub fn set_mickey_ratio(&self, x: u16, y: u16) {
+        int_33h_ax_000Fh_set_mickey_ratio(x, y);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MouseHookErr {
+    AlreadyInstalled,
+    Lock(AxErr),
+    Callback(AxErr),
+    Dpmi(DpmiErr),
+}
+
+impl From<InstallErr> for MouseHookErr {
+    fn from(e: InstallErr) -> MouseHookErr {
+        match e {
+            InstallErr::AlreadyInstalled => MouseHookErr::AlreadyInstalled,
+            InstallErr::Lock(e) => MouseHookErr::Lock(e),
+        }
+    }
+}
+
+impl From<AxErr> for MouseHookErr {
+    fn from(e: AxErr) -> MouseHookErr { MouseHookErr::Callback(e) }
+}
+
+impl From<DpmiErr> for MouseHookErr {
+    fn from(e: DpmiErr) -> MouseHookErr { MouseHookErr::Dpmi(e) }
+}
+
+/// Real-mode registers the host fills in before entering the callback.
+struct CallbackRegs(UnsafeCell<RmRegs>);
+
+// Only the callback touches the registers, with interrupts disabled.
+unsafe impl Sync for CallbackRegs { }
+
+type CallHandler = unsafe fn(*mut (), MouseEvent);
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static HANDLER_DATA: AtomicPtr<()> = AtomicPtr::new(null_mut());
+static HANDLER_CALL: AtomicPtr<()> = AtomicPtr::new(null_mut());
+static CALLBACK_REGS: CallbackRegs = CallbackRegs(UnsafeCell::new(RmRegs {
+    edi: 0, esi: 0, ebp: 0, zero: 0, ebx: 0, edx: 0, ecx: 0, eax: 0,
+    flags: 0, es: 0, ds: 0, fs: 0, gs: 0, ip: 0, cs: 0, sp: 0, ss: 0,
+}));
+static CALLBACK_SEGMENT: AtomicU16 = AtomicU16::new(0);
+static CALLBACK_OFFSET: AtomicU16 = AtomicU16::new(0);
+static OLD_MASK: AtomicU16 = AtomicU16::new(0);
+static OLD_SEGMENT: AtomicU16 = AtomicU16::new(0);
+static OLD_OFFSET: AtomicU16 = AtomicU16::new(0);
+static STACK: IsrStack = IsrStack::new();
+
+unsafe fn call_handler<F: FnMut(MouseEvent)>(data: *mut (), event: MouseEvent) {
+    unsafe { (*(data as *mut F))(event) }
+}
+
+extern "C" fn mouse_callback() {
+    let regs = unsafe { &*CALLBACK_REGS.0.get() };
+    let event = MouseEvent {
+        events: regs.eax as u16,
+        buttons: regs.ebx as u16,
+        x: regs.ecx as u16,
+        y: regs.edx as u16,
+        mickeys_x: regs.esi as i16,
+        mickeys_y: regs.edi as i16,
+    };
+    let call = HANDLER_CALL.load(Ordering::Acquire);
+    if call.is_null() { return; }
+    let call: CallHandler = unsafe { transmute::<*mut (), CallHandler>(call) };
+    unsafe { call(HANDLER_DATA.load(Ordering::Relaxed), event) };
+}
+
+#[cfg(not(dos))]
+unsafe extern "C" fn mouse_callback_entry() {
+    let _ = mouse_callback;
+    panic!("cfg(target_os=\"dos\")");
+}
+
+/// Entered by the host with DS:ESI at the real-mode stack and ES:EDI at [`CALLBACK_REGS`].
+/// Pops the driver's far return address into the registers, as a real-mode `retf` would.
+#[cfg(dos)]
+#[unsafe(naked)]
+unsafe extern "C" fn mouse_callback_entry() {
+    naked_asm!(
+        "mov eax, dword ptr [esi]",
+        "mov dword ptr es:[edi + {ip}], eax",
+        "add word ptr es:[edi + {sp}], 4",
+        "push es",
+        "push edi",
+        "mov ds, word ptr cs:[{ds}]",
+        "mov es, word ptr cs:[{ds}]",
+        "mov ecx, esp",
+        "mov edx, ss",
+        "mov ss, word ptr cs:[{ds}]",
+        "lea esp, [{stack} + {stack_size}]",
+        "push edx",
+        "push ecx",
+        "cld",
+        "call {handler}",
+        "pop ecx",
+        "pop edx",
+        "mov ss, dx",
+        "mov esp, ecx",
+        "pop edi",
+        "pop es",
+        "iretd",
+        ip = const offset_of!(RmRegs, ip),
+        sp = const offset_of!(RmRegs, sp),
+        ds = sym ISR_DS,
+        stack = sym STACK,
+        stack_size = const ISR_STACK_SIZE,
+        handler = sym mouse_callback,
+    );
+}
+
+/// Routes driver events to a Rust closure through a real-mode callback while alive.
+/// Only one hook can be installed at a time.
+///
+/// The closure runs inside the driver's IRQ12 handling with interrupts disabled.
+pub struct MouseEventHook(());
+
+impl MouseEventHook {
+    /// `events` is a combination of `MOUSE_EVENT_*` flags selecting what calls `handler`.
+    pub fn install<F: FnMut(MouseEvent) + Send + 'static>(
+        _mouse: &Mouse,
+        events: u16,
+        handler: &'static mut F,
+    ) -> Result<MouseEventHook, MouseHookErr> {
+        isr_init()?;
+        if INSTALLED.swap(true, Ordering::Acquire) { return Err(MouseHookErr::AlreadyInstalled); }
+        let data = handler as *mut F;
+        lock_data(unsafe { &*data });
+        lock_data(&HANDLER_DATA);
+        lock_data(&HANDLER_CALL);
+        lock_data(&CALLBACK_REGS);
+        lock_data(&STACK);
+        HANDLER_DATA.store(data as *mut (), Ordering::Relaxed);
+        HANDLER_CALL.store(call_handler::<F> as CallHandler as *mut (), Ordering::Release);
+        let installed = Self::hook(events);
+        if installed.is_err() {
+            HANDLER_CALL.store(null_mut(), Ordering::Release);
+            INSTALLED.store(false, Ordering::Release);
+        }
+        installed
+    }
+
+    fn hook(events: u16) -> Result<MouseEventHook, MouseHookErr> {
+        let entry = PmFarPtr::new(code_selector(), mouse_callback_entry as *const () as usize as u32);
+        let regs = PmFarPtr::new(data_selector(), CALLBACK_REGS.0.get() as usize as u32);
+        let callback = int_31h_ax_0303h_alloc_rm_callback(entry, regs)?.cx_dx_rm_addr;
+        let old = match int_33h_ax_0014h_swap_handler(events, callback) {
+            Ok(old) => old,
+            Err(e) => {
+                let _ = int_31h_ax_0304h_free_rm_callback(callback);
+                return Err(e.into());
+            },
+        };
+        CALLBACK_SEGMENT.store(callback.segment, Ordering::Relaxed);
+        CALLBACK_OFFSET.store(callback.offset, Ordering::Relaxed);
+        OLD_MASK.store(old.cx_mask, Ordering::Relaxed);
+        OLD_SEGMENT.store(old.es_dx_handler.segment, Ordering::Relaxed);
+        OLD_OFFSET.store(old.es_dx_handler.offset, Ordering::Relaxed);
+        Ok(MouseEventHook(()))
+    }
+}
+
+/// Gives the driver its previous handler back and frees the callback if the hook is installed.
+pub(crate) fn uninstall() {
+    if INSTALLED.load(Ordering::Acquire) {
+        let old = RmFarPtr::new(OLD_SEGMENT.load(Ordering::Relaxed), OLD_OFFSET.load(Ordering::Relaxed));
+        let _ = int_33h_ax_000Ch_set_handler(OLD_MASK.load(Ordering::Relaxed), old);
+        HANDLER_CALL.store(null_mut(), Ordering::Release);
+        let callback = RmFarPtr::new(CALLBACK_SEGMENT.load(Ordering::Relaxed), CALLBACK_OFFSET.load(Ordering::Relaxed));
+        let _ = int_31h_ax_0304h_free_rm_callback(callback);
+        INSTALLED.store(false, Ordering::Release);
+    }
+}
+
+impl Drop for MouseEventHook {
+    fn drop(&mut self) {
+        uninstall();
+    }
+}
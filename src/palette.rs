@@ -0,0 +1,150 @@
+use crate::*;
+use crate::vga::{VGA_DAC_DATA, VGA_DAC_READ_INDEX, VGA_DAC_WRITE_INDEX, wait_vretrace};
+use crate::xfer::XferBuf;
+
+/// Bytes of the red, green, blue table INT 10h AX=1012h and 1017h transfer for all colors.
+const DAC_TABLE_SIZE: usize = 256 * 3;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Rgb6 {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb6 {
+    pub const fn new(r: u8, g: u8, b: u8) -> Rgb6 {
+        Rgb6 { r: r & 0x3F, g: g & 0x3F, b: b & 0x3F }
+    }
+
+    pub const fn from_rgb8(r: u8, g: u8, b: u8) -> Rgb6 {
+        Rgb6 { r: r >> 2, g: g >> 2, b: b >> 2 }
+    }
+
+    pub const fn to_rgb8(self) -> (u8, u8, u8) {
+        ((self.r << 2) | (self.r >> 4), (self.g << 2) | (self.g >> 4), (self.b << 2) | (self.b >> 4))
+    }
+
+    pub const fn lerp(self, other: Rgb6, step: u16, steps: u16) -> Rgb6 {
+        const fn lerp(a: u8, b: u8, step: u16, steps: u16) -> u8 {
+            let a = a as i32;
+            let b = b as i32;
+            (a + (b - a) * step as i32 / steps as i32) as u8
+        }
+        if steps == 0 { return other; }
+        Rgb6 {
+            r: lerp(self.r, other.r, step, steps),
+            g: lerp(self.g, other.g, step, steps),
+            b: lerp(self.b, other.b, step, steps),
+        }
+    }
+}
+
+pub fn dac_write(first: u8, colors: &[Rgb6]) {
+    assert!(first as usize + colors.len() <= 256);
+    unsafe {
+        port_out_u8(VGA_DAC_WRITE_INDEX, first);
+        for color in colors {
+            port_out_u8(VGA_DAC_DATA, color.r);
+            port_out_u8(VGA_DAC_DATA, color.g);
+            port_out_u8(VGA_DAC_DATA, color.b);
+        }
+    }
+}
+
+pub fn dac_read(first: u8, colors: &mut [Rgb6]) {
+    assert!(first as usize + colors.len() <= 256);
+    unsafe {
+        port_out_u8(VGA_DAC_READ_INDEX, first);
+        for color in colors {
+            color.r = port_in_u8(VGA_DAC_DATA);
+            color.g = port_in_u8(VGA_DAC_DATA);
+            color.b = port_in_u8(VGA_DAC_DATA);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Palette(pub [Rgb6; 256]);
+
+impl Default for Palette {
+    fn default() -> Palette { Palette([Rgb6::default(); 256]) }
+}
+
+impl Palette {
+    pub fn read() -> Palette {
+        let mut palette = Palette::default();
+        dac_read(0, &mut palette.0);
+        palette
+    }
+
+    pub fn load(&self) {
+        dac_write(0, &self.0);
+    }
+
+    pub fn load_range(&self, first: u8, count: u16) {
+        dac_write(first, &self.0[first as usize .. first as usize + count as usize]);
+    }
+
+    pub fn read_bios(xfer: &XferBuf) -> Result<Palette, DpmiErr> {
+        assert!(xfer.len() as usize >= DAC_TABLE_SIZE, "transfer buffer is too small");
+        int_10h_ax_1017h_dac_block(0, 256, xfer.ptr())?;
+        let mut table = [0u8; DAC_TABLE_SIZE];
+        xfer.slice().read(0, &mut table);
+        let mut palette = Palette::default();
+        for (color, rgb) in palette.0.iter_mut().zip(table.chunks_exact(3)) {
+            *color = Rgb6 { r: rgb[0], g: rgb[1], b: rgb[2] };
+        }
+        Ok(palette)
+    }
+
+    pub fn load_bios(&self, xfer: &XferBuf) -> Result<(), DpmiErr> {
+        let mut table = [0u8; DAC_TABLE_SIZE];
+        for (color, rgb) in self.0.iter().zip(table.chunks_exact_mut(3)) {
+            rgb.copy_from_slice(&[color.r, color.g, color.b]);
+        }
+        xfer.copy_in(0, &table);
+        int_10h_ax_1012h_set_dac_block(0, 256, xfer.ptr())
+    }
+
+    pub fn fill(&mut self, color: Rgb6) {
+        self.0.fill(color);
+    }
+
+    pub fn blend(&self, target: &Palette, step: u16, steps: u16) -> Palette {
+        let mut palette = self.clone();
+        for (color, &target) in palette.0.iter_mut().zip(target.0.iter()) {
+            *color = color.lerp(target, step, steps);
+        }
+        palette
+    }
+
+    pub fn fade(&self, target: &Palette, steps: u16) {
+        for step in 1 ..= steps {
+            let palette = self.blend(target, step, steps);
+            wait_vretrace();
+            palette.load();
+        }
+    }
+
+    pub fn fade_in(&self, steps: u16) {
+        Palette::default().fade(self, steps);
+    }
+
+    pub fn fade_out(&self, steps: u16) {
+        self.fade(&Palette::default(), steps);
+    }
+
+    pub fn rotate(&mut self, first: u8, count: u16, by: i16) {
+        let range = &mut self.0[first as usize .. first as usize + count as usize];
+        if range.is_empty() { return; }
+        let by = by.rem_euclid(count as i16) as usize;
+        range.rotate_right(by);
+    }
+
+    pub fn cycle(&mut self, first: u8, count: u16, by: i16) {
+        self.rotate(first, count, by);
+        wait_vretrace();
+        self.load_range(first, count);
+    }
+}
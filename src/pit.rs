@@ -0,0 +1,135 @@
+use crate::*;
+
+/// PIT input clock in Hz.
+pub const PIT_HZ: u32 = 1193182;
+
+pub const PIT_CH0_DATA: u16 = 0x40;
+pub const PIT_CH1_DATA: u16 = 0x41;
+pub const PIT_CH2_DATA: u16 = 0x42;
+pub const PIT_COMMAND: u16 = 0x43;
+
+/// Largest divisor, programmed as a reload value of zero; the BIOS uses it for 18.2 Hz.
+pub const PIT_MAX_DIVISOR: u32 = 0x10000;
+
+const PIT_ACCESS_LOW_HIGH: u8 = 0x30;
+const PIT_LATCH: u8 = 0x00;
+
+/// 8254 read-back: latch status and count, then the channel bit.
+const PIT_READ_BACK: u8 = 0xC0;
+const PIT_STATUS_OUT: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum PitChannel {
+    /// Drives IRQ0.
+    Ch0 = 0,
+    /// DRAM refresh on older machines.
+    Ch1 = 1,
+    /// Drives the PC speaker.
+    Ch2 = 2,
+}
+
+impl PitChannel {
+    pub const fn data_port(self) -> u16 { PIT_CH0_DATA + self as u16 }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum PitMode {
+    InterruptOnTerminalCount = 0,
+    OneShot = 1,
+    RateGenerator = 2,
+    SquareWave = 3,
+    SoftwareStrobe = 4,
+    HardwareStrobe = 5,
+}
+
+impl PitMode {
+    /// Modes 6 and 7 read back as 2 and 3.
+    pub const fn from_status(status: u8) -> PitMode {
+        match (status >> 1) & 0x07 {
+            0 => PitMode::InterruptOnTerminalCount,
+            1 => PitMode::OneShot,
+            2 | 6 => PitMode::RateGenerator,
+            3 | 7 => PitMode::SquareWave,
+            4 => PitMode::SoftwareStrobe,
+            _ => PitMode::HardwareStrobe,
+        }
+    }
+}
+
+/// The divisor nearest to `hz`, clamped to what the PIT can count.
+pub const fn divisor_for_hz(hz: u32) -> u32 {
+    assert!(hz != 0);
+    let divisor = (PIT_HZ + hz / 2) / hz;
+    if divisor < 1 { 1 } else if divisor > PIT_MAX_DIVISOR { PIT_MAX_DIVISOR } else { divisor }
+}
+
+pub const fn hz_for_divisor(divisor: u32) -> u32 {
+    PIT_HZ / divisor
+}
+
+/// Sets the mode and the divisor, 1 to [`PIT_MAX_DIVISOR`], of a binary-counting channel.
+pub fn configure(channel: PitChannel, mode: PitMode, divisor: u32) {
+    assert!((1 ..= PIT_MAX_DIVISOR).contains(&divisor));
+    unsafe { port_out_u8(PIT_COMMAND, ((channel as u8) << 6) | PIT_ACCESS_LOW_HIGH | ((mode as u8) << 1)); }
+    set_reload(channel, divisor);
+}
+
+/// Writes a new divisor to a channel [`configure`] set up, keeping its mode. In the periodic
+/// modes the counter picks it up when the current period ends.
+pub fn set_reload(channel: PitChannel, divisor: u32) {
+    assert!((1 ..= PIT_MAX_DIVISOR).contains(&divisor));
+    let reload = divisor as u16;
+    unsafe {
+        port_out_u8(channel.data_port(), reload as u8);
+        port_out_u8(channel.data_port(), (reload >> 8) as u8);
+    }
+}
+
+fn read_latched(channel: PitChannel) -> u32 {
+    let (low, high) = unsafe { (port_in_u8(channel.data_port()), port_in_u8(channel.data_port())) };
+    match ((high as u32) << 8) | low as u32 {
+        0 => PIT_MAX_DIVISOR,
+        count => count,
+    }
+}
+
+/// The current count, with zero read as [`PIT_MAX_DIVISOR`].
+pub fn count(channel: PitChannel) -> u32 {
+    unsafe { port_out_u8(PIT_COMMAND, ((channel as u8) << 6) | PIT_LATCH); }
+    read_latched(channel)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct PitStatus {
+    pub mode: PitMode,
+    /// Level of the channel's output pin.
+    pub out: bool,
+    pub count: u32,
+}
+
+/// Latches mode, output and count together with the 8254 read-back command.
+pub fn status(channel: PitChannel) -> PitStatus {
+    unsafe { port_out_u8(PIT_COMMAND, PIT_READ_BACK | (2 << channel as u8)); }
+    let status = unsafe { port_in_u8(channel.data_port()) };
+    PitStatus {
+        mode: PitMode::from_status(status),
+        out: status & PIT_STATUS_OUT != 0,
+        count: read_latched(channel),
+    }
+}
+
+impl PitStatus {
+    /// PIT clocks since the channel last reloaded `divisor`.
+    ///
+    /// In mode 3 the count runs down twice per period, by two, and the output pin tells the halves apart.
+    pub fn clocks_into_period(&self, divisor: u32) -> u32 {
+        let remaining = divisor.saturating_sub(self.count);
+        match self.mode {
+            PitMode::SquareWave if self.out => remaining / 2,
+            PitMode::SquareWave => divisor / 2 + remaining / 2,
+            _ => remaining,
+        }
+    }
+}
@@ -0,0 +1,216 @@
+#[cfg(dos)]
+use core::arch::{asm, naked_asm};
+#[cfg(dos)]
+use memoffset::offset_of;
+use crate::*;
+use crate::far_mem::copy_to_far;
+
+const THUNK_PARAGRAPHS: u16 = 0x30;
+#[cfg(dos)]
+const THUNK_STACK_TOP: u16 = THUNK_PARAGRAPHS * 16;
+
+const THUNK_RM_PROC: u16 = 0x00;
+const THUNK_RM_TO_PM: u16 = 0x04;
+const THUNK_CODE: u16 = 0x18;
+
+// call far [cs:0000]
+// mov ax, [cs:0010]
+// mov cx, [cs:0012]
+// mov dx, [cs:0014]
+// mov ebx, [cs:0008]
+// mov si, [cs:0016]
+// mov edi, [cs:000C]
+// jmp far [cs:0004]
+const THUNK_CODE_BYTES: [u8; 41] = [
+    0x2E, 0xFF, 0x1E, 0x00, 0x00,
+    0x2E, 0xA1, 0x10, 0x00,
+    0x2E, 0x8B, 0x0E, 0x12, 0x00,
+    0x2E, 0x8B, 0x16, 0x14, 0x00,
+    0x66, 0x2E, 0x8B, 0x1E, 0x08, 0x00,
+    0x2E, 0x8B, 0x36, 0x16, 0x00,
+    0x66, 0x2E, 0x8B, 0x3E, 0x0C, 0x00,
+    0x2E, 0xFF, 0x2E, 0x04, 0x00,
+];
+
+#[cfg(dos)]
+const THUNK_PM_ESP: u16 = 0x08;
+#[cfg(dos)]
+const THUNK_PM_EIP: u16 = 0x0C;
+#[cfg(dos)]
+const THUNK_PM_DS: u16 = 0x10;
+#[cfg(dos)]
+const THUNK_PM_ES: u16 = 0x12;
+#[cfg(dos)]
+const THUNK_PM_SS: u16 = 0x14;
+#[cfg(dos)]
+const THUNK_PM_CS: u16 = 0x16;
+
+pub struct RawSwitch {
+    state: StateSaveAddrs,
+    switch: RawSwitchAddrs,
+    thunk: RmAlloc,
+}
+
+impl RawSwitch {
+    pub fn new() -> Result<RawSwitch, AllocErr> {
+        let state = int_31h_ax_0305h_state_save_addrs();
+        let switch = int_31h_ax_0306h_raw_switch_addrs();
+        let thunk = int_31h_ax_0100h_rm_alloc(THUNK_PARAGRAPHS)?;
+        write_thunk(thunk.dx_selector, THUNK_RM_TO_PM, &switch.bx_cx_rm_to_pm.to_u32().to_le_bytes());
+        write_thunk(thunk.dx_selector, THUNK_CODE, &THUNK_CODE_BYTES);
+        Ok(RawSwitch { state, switch, thunk })
+    }
+
+    pub fn state_buf_size(&self) -> usize {
+        self.state.ax_buf_size as usize
+    }
+
+    pub fn save_state(&self, buf: &mut [u8]) {
+        assert!(buf.len() >= self.state_buf_size());
+        if self.state.ax_buf_size != 0 {
+            call_state_proc(&self.state, 0, buf.as_mut_ptr());
+        }
+    }
+
+    pub fn restore_state(&self, buf: &[u8]) {
+        assert!(buf.len() >= self.state_buf_size());
+        if self.state.ax_buf_size != 0 {
+            call_state_proc(&self.state, 1, buf.as_ptr() as *mut u8);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `rm_proc` should point to a real-mode far procedure.
+    /// The procedure is called with `DS = ES = SS` set to a private conventional memory block,
+    /// it can use the stack freely, but should return with `retf`.
+    pub unsafe fn call_rm(&self, rm_proc: RmFarPtr, state_buf: &mut [u8]) {
+        self.save_state(state_buf);
+        write_thunk(self.thunk.dx_selector, THUNK_RM_PROC, &rm_proc.to_u32().to_le_bytes());
+        let frame = RawCallFrame {
+            pm_to_rm: self.switch.si_edi_pm_to_rm,
+            thunk_segment: self.thunk.ax_segment,
+            thunk_selector: self.thunk.dx_selector,
+        };
+        unsafe { raw_call(&frame); }
+        self.restore_state(state_buf);
+    }
+}
+
+impl Drop for RawSwitch {
+    fn drop(&mut self) {
+        let _ = int_31h_ax_0101h_rm_free(self.thunk.dx_selector);
+    }
+}
+
+#[repr(C)]
+struct RawCallFrame {
+    pm_to_rm: PmFarPtr,
+    thunk_segment: u16,
+    thunk_selector: u16,
+}
+
+fn write_thunk(selector: u16, offset: u16, bytes: &[u8]) {
+    // The thunk lives in a DOS block the switch allocated for itself, sized for the whole thunk.
+    unsafe { copy_to_far(selector, offset as u32, bytes); }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+fn call_state_proc(state: &StateSaveAddrs, al_restore: u8, edi_buf: *mut u8) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+fn call_state_proc(state: &StateSaveAddrs, al_restore: u8, edi_buf: *mut u8) {
+    unsafe {
+        asm!(
+            "call fword ptr [{proc:e}]",
+            proc = in(reg) &state.si_edi_pm_addr as *const PmFarPtr,
+            in("al") al_restore,
+            in("edi") edi_buf,
+        );
+    }
+}
+
+/// Dwords `raw_call` pushes before reading its argument: ebp, ebx, esi, edi, eflags and four
+/// selectors, which go through EDX because `push ds` and friends assemble as 16-bit pushes.
+#[cfg(dos)]
+const RAW_CALL_SAVED_DWORDS: usize = 9;
+/// The argument sits above the saved registers and the return address.
+#[cfg(dos)]
+const RAW_CALL_FRAME_ARG: usize = (RAW_CALL_SAVED_DWORDS + 1) * 4;
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+unsafe extern "C" fn raw_call(frame: *const RawCallFrame) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[unsafe(naked)]
+unsafe extern "C" fn raw_call(frame: *const RawCallFrame) {
+    naked_asm!(
+        "push ebp",
+        "push ebx",
+        "push esi",
+        "push edi",
+        "pushfd",
+        "mov edx, ds",
+        "push edx",
+        "mov edx, es",
+        "push edx",
+        "mov edx, fs",
+        "push edx",
+        "mov edx, gs",
+        "push edx",
+        "mov eax, [esp + {frame_arg}]",
+        "mov es, word ptr [eax + {thunk_selector}]",
+        "mov es:[{pm_esp}], esp",
+        "lea edx, [2f]",
+        "mov es:[{pm_eip}], edx",
+        "mov word ptr es:[{pm_ds}], ds",
+        "mov word ptr es:[{pm_es}], ds",
+        "mov word ptr es:[{pm_ss}], ss",
+        "mov word ptr es:[{pm_cs}], cs",
+        "movzx edx, word ptr [eax + {pm_to_rm} + {selector}]",
+        "push edx",
+        "push dword ptr [eax + {pm_to_rm} + {offset}]",
+        "movzx edx, word ptr [eax + {thunk_segment}]",
+        "mov ebx, {stack_top}",
+        "mov ecx, edx",
+        "mov esi, edx",
+        "mov edi, {code}",
+        "mov eax, edx",
+        "retf",
+        "2:",
+        "pop edx",
+        "mov gs, edx",
+        "pop edx",
+        "mov fs, edx",
+        "pop edx",
+        "mov es, edx",
+        "pop edx",
+        "mov ds, edx",
+        "popfd",
+        "pop edi",
+        "pop esi",
+        "pop ebx",
+        "pop ebp",
+        "ret",
+        frame_arg = const RAW_CALL_FRAME_ARG,
+        thunk_selector = const offset_of!(RawCallFrame, thunk_selector),
+        thunk_segment = const offset_of!(RawCallFrame, thunk_segment),
+        pm_to_rm = const offset_of!(RawCallFrame, pm_to_rm),
+        selector = const offset_of!(PmFarPtr, selector),
+        offset = const offset_of!(PmFarPtr, offset),
+        pm_esp = const THUNK_PM_ESP,
+        pm_eip = const THUNK_PM_EIP,
+        pm_ds = const THUNK_PM_DS,
+        pm_es = const THUNK_PM_ES,
+        pm_ss = const THUNK_PM_SS,
+        pm_cs = const THUNK_PM_CS,
+        stack_top = const THUNK_STACK_TOP,
+        code = const THUNK_CODE,
+    );
+}
@@ -0,0 +1,127 @@
+use core::fmt::{self, Display, Formatter};
+use crate::*;
+use crate::date_time::{InvalidDateTime, days_in_month};
+use crate::time::{BDA_TIMER_TICKS, bda};
+
+/// BIOS timer ticks in a day, at 1193180 / 65536 Hz.
+pub const TICKS_PER_DAY: u32 = 0x1800B0;
+
+/// Nonzero once the tick counter has passed midnight, until DOS or INT 1Ah AH=00h reads it.
+pub const BDA_MIDNIGHT_FLAG: u32 = 0x70;
+
+pub const fn bcd_to_bin(bcd: u8) -> Option<u8> {
+    let (high, low) = (bcd >> 4, bcd & 0x0F);
+    if high > 9 || low > 9 { return None; }
+    Some(high * 10 + low)
+}
+
+pub const fn bin_to_bcd(bin: u8) -> u8 {
+    assert!(bin < 100);
+    ((bin / 10) << 4) | (bin % 10)
+}
+
+/// The tick counter since midnight, and whether midnight passed since DOS last read it.
+///
+/// Reads the BIOS data area rather than calling INT 1Ah AH=00h, which would clear the flag
+/// DOS needs to advance its date.
+pub fn ticks() -> (u32, bool) {
+    let bda = bda();
+    loop {
+        let ticks = bda.read_u32(BDA_TIMER_TICKS);
+        let midnight = bda.read_u8(BDA_MIDNIGHT_FLAG) != 0;
+        if bda.read_u32(BDA_TIMER_TICKS) == ticks {
+            return (ticks, midnight);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SetAlarmErr {
+    InvalidTime,
+    Alarm(AlarmErr),
+}
+
+impl From<AlarmErr> for SetAlarmErr {
+    fn from(e: AlarmErr) -> SetAlarmErr { SetAlarmErr::Alarm(e) }
+}
+
+const fn is_valid_time(hour: u8, minute: u8, second: u8) -> bool {
+    hour < 24 && minute < 60 && second < 60
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct RtcDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// Daylight saving time, as far as the RTC knows.
+    pub dst: bool,
+}
+
+impl RtcDateTime {
+    /// Reads the CMOS clock. `None` if it is not running or holds something other than BCD.
+    ///
+    /// The date is read on both sides of the time so a read across midnight is retried.
+    pub fn read() -> Option<RtcDateTime> {
+        loop {
+            let date = int_1Ah_ah_04h_rtc_date()?;
+            let time = int_1Ah_ah_02h_rtc_time()?;
+            let again = int_1Ah_ah_04h_rtc_date()?;
+            if again.dl_day_bcd != date.dl_day_bcd { continue; }
+            return Some(RtcDateTime {
+                year: bcd_to_bin(date.ch_century_bcd)? as u16 * 100 + bcd_to_bin(date.cl_year_bcd)? as u16,
+                month: bcd_to_bin(date.dh_month_bcd)?,
+                day: bcd_to_bin(date.dl_day_bcd)?,
+                hour: bcd_to_bin(time.ch_hours_bcd)?,
+                minute: bcd_to_bin(time.cl_minutes_bcd)?,
+                second: bcd_to_bin(time.dh_seconds_bcd)?,
+                dst: time.dl_dst != 0,
+            });
+        }
+    }
+
+    /// Writes the CMOS clock. DOS keeps its own time, which this does not change.
+    pub fn set(&self) -> Result<(), InvalidDateTime> {
+        if self.year >= 10000 || !(1 ..= 12).contains(&self.month) { return Err(InvalidDateTime); }
+        if self.day < 1 || self.day > days_in_month(self.year, self.month) { return Err(InvalidDateTime); }
+        if !is_valid_time(self.hour, self.minute, self.second) { return Err(InvalidDateTime); }
+        int_1Ah_ah_05h_set_rtc_date(&RtcDate {
+            ch_century_bcd: bin_to_bcd((self.year / 100) as u8),
+            cl_year_bcd: bin_to_bcd((self.year % 100) as u8),
+            dh_month_bcd: bin_to_bcd(self.month),
+            dl_day_bcd: bin_to_bcd(self.day),
+        });
+        int_1Ah_ah_03h_set_rtc_time(&RtcTime {
+            ch_hours_bcd: bin_to_bcd(self.hour),
+            cl_minutes_bcd: bin_to_bcd(self.minute),
+            dh_seconds_bcd: bin_to_bcd(self.second),
+            dl_dst: self.dst as u8,
+        });
+        Ok(())
+    }
+}
+
+/// Formats as `YYYY-MM-DD HH:MM:SS`.
+impl Display for RtcDateTime {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Arms the RTC alarm, which raises INT 4Ah once at the given time of day.
+pub fn set_alarm(hour: u8, minute: u8, second: u8) -> Result<(), SetAlarmErr> {
+    if !is_valid_time(hour, minute, second) { return Err(SetAlarmErr::InvalidTime); }
+    int_1Ah_ah_06h_set_alarm(bin_to_bcd(hour), bin_to_bcd(minute), bin_to_bcd(second))?;
+    Ok(())
+}
+
+pub fn cancel_alarm() {
+    int_1Ah_ah_07h_cancel_alarm();
+}
@@ -0,0 +1,321 @@
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU8, Ordering};
+use crate::*;
+use crate::isr::{lock_data, lock_slice};
+use crate::pit::*;
+
+pub const SPEAKER_CONTROL: u16 = 0x61;
+
+/// Gate input of PIT channel 2.
+const SPEAKER_GATE: u8 = 0x01;
+/// Connects channel 2's output to the speaker.
+const SPEAKER_DATA: u8 = 0x02;
+
+/// Channel 2, low byte only, mode 0: each write starts one pulse of that many clocks.
+const PIT_CH2_PULSE: u8 = 0x90;
+
+const IDLE: u8 = 0;
+const BUSY: u8 = 1;
+
+/// The speaker operations the players need, so they can be driven by something other than ports.
+pub trait SpeakerHw {
+    /// Plays a square wave of [`PIT_HZ`]` / divisor`.
+    fn tone(&mut self, divisor: u32);
+
+    fn silence(&mut self);
+
+    /// Switches channel 2 to single pulses for [`SpeakerHw::pulse`].
+    fn pwm_start(&mut self);
+
+    /// Drives the speaker cone for `width` PIT clocks.
+    fn pulse(&mut self, width: u8);
+}
+
+/// The speaker behind PIT channel 2 and port 61h.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct PcSpeaker;
+
+impl PcSpeaker {
+    fn connect(&self, connected: bool) {
+        let control = unsafe { port_in_u8(SPEAKER_CONTROL) };
+        let control = if connected {
+            control | SPEAKER_GATE | SPEAKER_DATA
+        } else {
+            control & !(SPEAKER_GATE | SPEAKER_DATA)
+        };
+        unsafe { port_out_u8(SPEAKER_CONTROL, control); }
+    }
+}
+
+impl SpeakerHw for PcSpeaker {
+    fn tone(&mut self, divisor: u32) {
+        // A square wave needs a count of at least 2.
+        configure(PitChannel::Ch2, PitMode::SquareWave, divisor.max(2));
+        self.connect(true);
+    }
+
+    fn silence(&mut self) {
+        self.connect(false);
+    }
+
+    fn pwm_start(&mut self) {
+        unsafe { port_out_u8(PIT_COMMAND, PIT_CH2_PULSE); }
+        self.connect(true);
+    }
+
+    fn pulse(&mut self, width: u8) {
+        unsafe { port_out_u8(PIT_CH2_DATA, width); }
+    }
+}
+
+/// Plays `hz` until [`silence`] is called.
+pub fn tone(hz: u32) {
+    PcSpeaker.tone(divisor_for_hz(hz));
+}
+
+pub fn silence() {
+    PcSpeaker.silence();
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Note {
+    /// Zero for a rest.
+    pub hz: u32,
+    pub ms: u32,
+}
+
+impl Note {
+    pub const fn new(hz: u32, ms: u32) -> Note { Note { hz, ms } }
+
+    pub const fn rest(ms: u32) -> Note { Note { hz: 0, ms } }
+}
+
+/// Steps through a list of notes, one call of [`MelodyPlayer::tick`] per timer tick.
+#[derive(Debug, Clone)]
+pub struct MelodyPlayer<'a> {
+    notes: &'a [Note],
+    tick_hz: u32,
+    next: usize,
+    ticks_left: u32,
+}
+
+impl<'a> MelodyPlayer<'a> {
+    pub fn new(notes: &'a [Note], tick_hz: u32) -> MelodyPlayer<'a> {
+        assert!(tick_hz != 0);
+        MelodyPlayer { notes, tick_hz, next: 0, ticks_left: 0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next == self.notes.len() && self.ticks_left == 0
+    }
+
+    /// Advances by one tick, starting the next note when the current one ends.
+    /// Returns `false`, with the speaker silenced, once the melody is over.
+    pub fn tick(&mut self, hw: &mut impl SpeakerHw) -> bool {
+        if self.ticks_left == 0 {
+            let Some(note) = self.notes.get(self.next) else {
+                hw.silence();
+                return false;
+            };
+            self.next += 1;
+            if note.hz == 0 {
+                hw.silence();
+            } else {
+                hw.tone(divisor_for_hz(note.hz));
+            }
+            self.ticks_left = ((note.ms as u64 * self.tick_hz as u64 / 1000) as u32).max(1);
+        }
+        self.ticks_left -= 1;
+        true
+    }
+
+    pub fn stop(&mut self, hw: &mut impl SpeakerHw) {
+        self.next = self.notes.len();
+        self.ticks_left = 0;
+        hw.silence();
+    }
+}
+
+/// Plays 8-bit unsigned samples by pulse width modulation, one sample per timer tick.
+///
+/// A pulse can not outlast the tick, so the timer divisor bounds the resolution: at 16 kHz
+/// about 75 levels remain.
+#[derive(Debug, Clone)]
+pub struct PwmPlayer<'a> {
+    samples: &'a [u8],
+    timer_divisor: u32,
+    next: usize,
+}
+
+impl<'a> PwmPlayer<'a> {
+    /// `timer_divisor` is the channel 0 divisor of the tick driving the player, at most 100h.
+    pub fn new(samples: &'a [u8], timer_divisor: u32) -> PwmPlayer<'a> {
+        assert!((2 ..= 0x100).contains(&timer_divisor));
+        PwmPlayer { samples, timer_divisor, next: 0 }
+    }
+
+    pub fn is_finished(&self) -> bool { self.next == self.samples.len() }
+
+    pub fn tick(&mut self, hw: &mut impl SpeakerHw) -> bool {
+        let Some(&sample) = self.samples.get(self.next) else {
+            hw.silence();
+            return false;
+        };
+        if self.next == 0 {
+            hw.pwm_start();
+        }
+        self.next += 1;
+        hw.pulse((1 + sample as u32 * (self.timer_divisor - 2) / 0xFF) as u8);
+        true
+    }
+}
+
+struct MelodyCell(UnsafeCell<Option<MelodyPlayer<'static>>>);
+
+// Access is serialized through `MELODY_STATE`.
+unsafe impl Sync for MelodyCell { }
+
+static MELODY_STATE: AtomicU8 = AtomicU8::new(IDLE);
+static MELODY: MelodyCell = MelodyCell(UnsafeCell::new(None));
+
+/// Runs `f` unless the melody is in use. Only the timer interrupt can find it in use, since
+/// the interrupt runs to completion before the program resumes.
+fn with_melody<R>(f: impl FnOnce(&mut Option<MelodyPlayer<'static>>) -> R) -> Option<R> {
+    if MELODY_STATE.compare_exchange(IDLE, BUSY, Ordering::Acquire, Ordering::Relaxed).is_err() {
+        return None;
+    }
+    let result = f(unsafe { &mut *MELODY.0.get() });
+    MELODY_STATE.store(IDLE, Ordering::Release);
+    Some(result)
+}
+
+/// Starts playing `notes` in the background, replacing any melody already playing.
+/// [`melody_tick`] must be called at `tick_hz`, typically from a [`crate::timer_irq::TimerHandler`].
+pub fn play_melody(notes: &'static [Note], tick_hz: u32) {
+    lock_slice(notes);
+    lock_data(&MELODY_STATE);
+    lock_data(&MELODY);
+    with_melody(|melody| *melody = Some(MelodyPlayer::new(notes, tick_hz)));
+}
+
+/// Advances the background melody. Safe to call from an interrupt handler.
+pub fn melody_tick() {
+    with_melody(|melody| {
+        if melody.as_mut().is_some_and(|player| !player.tick(&mut PcSpeaker)) {
+            *melody = None;
+        }
+    });
+}
+
+pub fn is_melody_playing() -> bool {
+    with_melody(|melody| melody.is_some()).unwrap_or(true)
+}
+
+pub fn stop_melody() {
+    with_melody(|melody| *melody = None);
+    silence();
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    enum Op {
+        Tone(u32),
+        Silence,
+        PwmStart,
+        Pulse(u8),
+    }
+
+    #[derive(Default)]
+    struct Recorder(Vec<Op>);
+
+    impl SpeakerHw for Recorder {
+        fn tone(&mut self, divisor: u32) { self.0.push(Op::Tone(divisor)); }
+
+        fn silence(&mut self) { self.0.push(Op::Silence); }
+
+        fn pwm_start(&mut self) { self.0.push(Op::PwmStart); }
+
+        fn pulse(&mut self, width: u8) { self.0.push(Op::Pulse(width)); }
+    }
+
+    fn ticks_until_finished(player: &mut MelodyPlayer, hw: &mut Recorder) -> u32 {
+        let mut ticks = 0;
+        while player.tick(hw) {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn note_lasts_its_ticks() {
+        let notes = [Note::new(440, 100), Note::new(880, 50)];
+        let mut hw = Recorder::default();
+        let mut player = MelodyPlayer::new(&notes, 1000);
+        assert_eq!(ticks_until_finished(&mut player, &mut hw), 150);
+        assert_eq!(hw.0, [Op::Tone(divisor_for_hz(440)), Op::Tone(divisor_for_hz(880)), Op::Silence]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn short_note_lasts_one_tick() {
+        let notes = [Note::new(440, 1), Note::new(440, 0)];
+        let mut hw = Recorder::default();
+        let mut player = MelodyPlayer::new(&notes, 18);
+        assert_eq!(ticks_until_finished(&mut player, &mut hw), 2);
+    }
+
+    #[test]
+    fn rest_silences() {
+        let notes = [Note::new(440, 20), Note::rest(30), Note::new(440, 10)];
+        let mut hw = Recorder::default();
+        let mut player = MelodyPlayer::new(&notes, 100);
+        assert_eq!(ticks_until_finished(&mut player, &mut hw), 6);
+        let tone = Op::Tone(divisor_for_hz(440));
+        assert_eq!(hw.0, [tone, Op::Silence, tone, Op::Silence]);
+    }
+
+    #[test]
+    fn finished_melody_stays_silent() {
+        let mut hw = Recorder::default();
+        let mut player = MelodyPlayer::new(&[], 100);
+        assert!(player.is_finished());
+        assert!(!player.tick(&mut hw));
+        assert!(!player.tick(&mut hw));
+        assert_eq!(hw.0, [Op::Silence, Op::Silence]);
+    }
+
+    #[test]
+    fn stop_ends_melody() {
+        let notes = [Note::new(440, 100), Note::new(880, 100)];
+        let mut hw = Recorder::default();
+        let mut player = MelodyPlayer::new(&notes, 100);
+        assert!(player.tick(&mut hw));
+        player.stop(&mut hw);
+        assert!(player.is_finished());
+        assert!(!player.tick(&mut hw));
+        assert_eq!(hw.0, [Op::Tone(divisor_for_hz(440)), Op::Silence, Op::Silence]);
+    }
+
+    #[test]
+    fn pwm_pulse_at_smallest_divisor() {
+        let mut hw = Recorder::default();
+        let mut player = PwmPlayer::new(&[0x00, 0x80, 0xFF], 2);
+        while player.tick(&mut hw) { }
+        assert_eq!(hw.0, [Op::PwmStart, Op::Pulse(1), Op::Pulse(1), Op::Pulse(1), Op::Silence]);
+    }
+
+    #[test]
+    fn pwm_pulse_at_largest_divisor() {
+        let mut hw = Recorder::default();
+        let mut player = PwmPlayer::new(&[0x00, 0x80, 0xFF], 0x100);
+        while player.tick(&mut hw) { }
+        assert_eq!(hw.0, [Op::PwmStart, Op::Pulse(1), Op::Pulse(128), Op::Pulse(255), Op::Silence]);
+        assert!(player.is_finished());
+    }
+}
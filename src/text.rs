@@ -0,0 +1,261 @@
+use core::cmp::min;
+use core::fmt;
+use crate::*;
+use crate::far_mem::FarSlice;
+
+pub const BDA_SEGMENT: u16 = 0x0040;
+pub const BDA_PAGE_SIZE: u32 = 0x4C;
+pub const BDA_ROWS_MINUS_ONE: u32 = 0x84;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Cell {
+    pub ch: u8,
+    pub attr: Attr,
+}
+
+impl Cell {
+    pub const fn new(ch: u8, attr: Attr) -> Cell {
+        Cell { ch, attr }
+    }
+
+    pub const fn from_u16(value: u16) -> Cell {
+        Cell { ch: value as u8, attr: Attr((value >> 8) as u8) }
+    }
+
+    pub const fn to_u16(self) -> u16 {
+        ((self.attr.0 as u16) << 8) | self.ch as u16
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TextScreenErr {
+    GraphicsMode(u8),
+    /// The page does not fit in the video segment.
+    BadPage(u8),
+    Selector(AxErr),
+}
+
+impl From<AxErr> for TextScreenErr {
+    fn from(e: AxErr) -> TextScreenErr { TextScreenErr::Selector(e) }
+}
+
+pub fn is_text_mode(al_mode: u8) -> bool {
+    matches!(al_mode & 0x7F, 0x00 ..= 0x03 | 0x07)
+}
+
+#[derive(Debug, Clone)]
+pub struct TextScreen {
+    mem: FarSlice,
+    mode: u8,
+    page: u8,
+    cols: u16,
+    rows: u16,
+}
+
+impl TextScreen {
+    pub fn new() -> Result<TextScreen, TextScreenErr> {
+        let mode = int_10h_ah_0Fh_video_mode();
+        TextScreen::with_page(mode.bh_active_page)
+    }
+
+    pub fn with_page(page: u8) -> Result<TextScreen, TextScreenErr> {
+        let mode = int_10h_ah_0Fh_video_mode();
+        if !is_text_mode(mode.al_mode) {
+            return Err(TextScreenErr::GraphicsMode(mode.al_mode));
+        }
+        let bda = FarSlice::rm_segment(BDA_SEGMENT)?;
+        let rows = match bda.read_u8(BDA_ROWS_MINUS_ONE) {
+            0 => 25,
+            rows_minus_one => rows_minus_one as u16 + 1,
+        };
+        let cols = mode.ah_cols as u16;
+        let page_size = match bda.read_u16(BDA_PAGE_SIZE) {
+            0 => (cols as u32) * (rows as u32) * 2,
+            page_size => page_size as u32,
+        };
+        let segment = if mode.al_mode & 0x7F == 0x07 { 0xB000 } else { 0xB800 };
+        let mem = FarSlice::rm_segment(segment)?
+            .slice(page as u32 * page_size, (cols as u32) * (rows as u32) * 2)
+            .ok_or(TextScreenErr::BadPage(page))?;
+        Ok(TextScreen { mem, mode: mode.al_mode, page, cols, rows })
+    }
+
+    pub fn mode(&self) -> u8 { self.mode }
+
+    pub fn page(&self) -> u8 { self.page }
+
+    pub fn cols(&self) -> u16 { self.cols }
+
+    pub fn rows(&self) -> u16 { self.rows }
+
+    pub fn mem(&self) -> &FarSlice { &self.mem }
+
+    fn offset(&self, x: u16, y: u16) -> u32 {
+        assert!(x < self.cols && y < self.rows, "text screen position out of bounds");
+        ((y as u32) * (self.cols as u32) + x as u32) * 2
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Cell {
+        Cell::from_u16(self.mem.read_u16(self.offset(x, y)))
+    }
+
+    pub fn set_cell(&self, x: u16, y: u16, cell: Cell) {
+        self.mem.write_u16(self.offset(x, y), cell.to_u16());
+    }
+
+    pub fn read_row(&self, x: u16, y: u16, cells: &mut [Cell]) {
+        let len = min(cells.len(), self.cols.saturating_sub(x) as usize);
+        for (i, cell) in cells[.. len].iter_mut().enumerate() {
+            *cell = self.cell(x + i as u16, y);
+        }
+    }
+
+    pub fn write_row(&self, x: u16, y: u16, cells: &[Cell]) {
+        let mut buf = [0u8; 512];
+        let len = min(cells.len(), self.cols.saturating_sub(x) as usize);
+        if len == 0 { return; }
+        for (i, cell) in cells[.. len].iter().enumerate() {
+            buf[2 * i .. 2 * i + 2].copy_from_slice(&cell.to_u16().to_le_bytes());
+        }
+        self.mem.write(self.offset(x, y), &buf[.. 2 * len]);
+    }
+
+    pub fn write_str(&self, x: u16, y: u16, s: &[u8], attr: Attr) {
+        for (i, &ch) in s.iter().take(self.cols.saturating_sub(x) as usize).enumerate() {
+            self.set_cell(x + i as u16, y, Cell { ch, attr });
+        }
+    }
+
+    pub fn fill_rect(&self, x: u16, y: u16, width: u16, height: u16, cell: Cell) {
+        let width = min(width, self.cols.saturating_sub(x));
+        let height = min(height, self.rows.saturating_sub(y));
+        for row in y .. y + height {
+            for col in x .. x + width {
+                self.set_cell(col, row, cell);
+            }
+        }
+    }
+
+    pub fn clear(&self, cell: Cell) {
+        self.fill_rect(0, 0, self.cols, self.rows, cell);
+    }
+
+    pub fn copy_rect(&self, src_x: u16, src_y: u16, width: u16, height: u16, dest_x: u16, dest_y: u16) {
+        let width = min(width, min(self.cols.saturating_sub(src_x), self.cols.saturating_sub(dest_x)));
+        let height = min(height, min(self.rows.saturating_sub(src_y), self.rows.saturating_sub(dest_y)));
+        if width == 0 || height == 0 { return; }
+        let mut buf = [0u8; 512];
+        let buf = &mut buf[.. 2 * width as usize];
+        let mut copy_row = |row: u16| {
+            self.mem.read(self.offset(src_x, src_y + row), buf);
+            self.mem.write(self.offset(dest_x, dest_y + row), buf);
+        };
+        if dest_y > src_y {
+            (0 .. height).rev().for_each(&mut copy_row);
+        } else {
+            (0 .. height).for_each(&mut copy_row);
+        }
+    }
+
+    pub fn scroll_up(&self, x: u16, y: u16, width: u16, height: u16, lines: u16, fill: Cell) {
+        let lines = min(lines, height);
+        self.copy_rect(x, y + lines, width, height - lines, x, y);
+        self.fill_rect(x, y + height - lines, width, lines, fill);
+    }
+
+    pub fn scroll_down(&self, x: u16, y: u16, width: u16, height: u16, lines: u16, fill: Cell) {
+        let lines = min(lines, height);
+        self.copy_rect(x, y, width, height - lines, x, y + lines);
+        self.fill_rect(x, y, width, lines, fill);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Console {
+    screen: TextScreen,
+    x: u16,
+    y: u16,
+    attr: Attr,
+}
+
+impl Console {
+    pub fn new(screen: TextScreen, attr: Attr) -> Console {
+        let cursor = int_10h_ah_03h_cursor(screen.page());
+        let x = min(cursor.dl_column as u16, screen.cols() - 1);
+        let y = min(cursor.dh_row as u16, screen.rows() - 1);
+        Console { screen, x, y, attr }
+    }
+
+    pub fn screen(&self) -> &TextScreen { &self.screen }
+
+    pub fn attr(&self) -> Attr { self.attr }
+
+    pub fn set_attr(&mut self, attr: Attr) { self.attr = attr; }
+
+    pub fn cursor(&self) -> (u16, u16) { (self.x, self.y) }
+
+    pub fn set_cursor(&mut self, x: u16, y: u16) {
+        self.x = min(x, self.screen.cols() - 1);
+        self.y = min(y, self.screen.rows() - 1);
+        self.sync_cursor();
+    }
+
+    pub fn sync_cursor(&self) {
+        int_10h_ah_02h_set_cursor_position(self.screen.page(), self.y as u8, self.x as u8);
+    }
+
+    pub fn clear(&mut self) {
+        self.screen.clear(Cell { ch: b' ', attr: self.attr });
+        self.set_cursor(0, 0);
+    }
+
+    fn new_line(&mut self) {
+        self.x = 0;
+        if self.y + 1 < self.screen.rows() {
+            self.y += 1;
+        } else {
+            let (cols, rows) = (self.screen.cols(), self.screen.rows());
+            self.screen.scroll_up(0, 0, cols, rows, 1, Cell { ch: b' ', attr: self.attr });
+        }
+    }
+
+    pub fn put_char(&mut self, ch: u8) {
+        match ch {
+            b'\n' => self.new_line(),
+            b'\r' => self.x = 0,
+            b'\x08' => self.x = self.x.saturating_sub(1),
+            b'\t' => {
+                let next = (self.x / 8 + 1) * 8;
+                if next >= self.screen.cols() {
+                    self.new_line();
+                } else {
+                    self.x = next;
+                }
+            },
+            ch => {
+                self.screen.set_cell(self.x, self.y, Cell { ch, attr: self.attr });
+                self.x += 1;
+                if self.x == self.screen.cols() {
+                    self.new_line();
+                }
+            },
+        }
+    }
+
+    pub fn put_bytes(&mut self, s: &[u8]) {
+        for &ch in s {
+            self.put_char(ch);
+        }
+        self.sync_cursor();
+    }
+}
+
+impl fmt::Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.put_char(if c.is_ascii() { c as u8 } else { b'?' });
+        }
+        self.sync_cursor();
+        Ok(())
+    }
+}
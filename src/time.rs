@@ -0,0 +1,144 @@
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+use core::sync::atomic::{AtomicU16, AtomicU32, AtomicU64, Ordering};
+use core::time::Duration;
+use crate::far_mem::FarSlice;
+use crate::pit::*;
+use crate::rtc::{BDA_MIDNIGHT_FLAG, TICKS_PER_DAY};
+use crate::text::BDA_SEGMENT;
+use crate::timer_irq::ch0_period;
+
+pub const BDA_TIMER_TICKS: u32 = 0x6C;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// How close to midnight a counter drop must start and end to pass for a wrap when DOS has
+/// already cleared the midnight flag: an hour.
+const MIDNIGHT_WINDOW: u32 = TICKS_PER_DAY / 24;
+
+static BDA_SELECTOR: AtomicU16 = AtomicU16::new(0);
+static LAST_TICKS: AtomicU32 = AtomicU32::new(0);
+/// Ticks added to the counter to continue across midnights and clock changes.
+static TICKS_OFFSET: AtomicU64 = AtomicU64::new(0);
+/// The latest value `now` returned.
+static LAST_INSTANT: AtomicU64 = AtomicU64::new(0);
+
+/// The BIOS data area, mapped on first use.
+pub(crate) fn bda() -> FarSlice {
+    let mut selector = BDA_SELECTOR.load(Ordering::Relaxed);
+    if selector == 0 {
+        selector = FarSlice::rm_segment(BDA_SEGMENT).expect("BDA selector").selector();
+        BDA_SELECTOR.store(selector, Ordering::Relaxed);
+    }
+    // The selector maps the BIOS data area, which Rust code does not own.
+    unsafe { FarSlice::new(selector) }.expect("BDA selector")
+}
+
+fn bios_ticks(bda: &FarSlice) -> u32 {
+    bda.read_u32(BDA_TIMER_TICKS)
+}
+
+/// A point on a monotonic clock counting PIT clocks, built from the BIOS tick counter.
+///
+/// The counter restarts at midnight; a wrap is noticed as long as `now` is called at least
+/// once a day. Setting the DOS time back also moves the counter back, which the clock skips
+/// over instead of taking it for midnight. PIT channel 0 must run at the BIOS rate, or be driven by [`crate::timer_irq::TimerDriver`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Instant(u64);
+
+impl Instant {
+    pub fn now() -> Instant {
+        let bda = bda();
+        let (ticks, midnight, clocks) = loop {
+            let ticks = bios_ticks(&bda);
+            let midnight = bda.read_u8(BDA_MIDNIGHT_FLAG) != 0;
+            let (divisor, phase) = ch0_period();
+            let clocks = status(PitChannel::Ch0).clocks_into_period(divisor);
+            if bios_ticks(&bda) == ticks && ch0_period() == (divisor, phase) {
+                break (ticks, midnight, (phase + clocks).min(PIT_MAX_DIVISOR - 1));
+            }
+        };
+        let last = LAST_TICKS.swap(ticks, Ordering::Relaxed);
+        if ticks < last {
+            let skipped = if midnight || (last >= TICKS_PER_DAY - MIDNIGHT_WINDOW && ticks < MIDNIGHT_WINDOW) {
+                TICKS_PER_DAY
+            } else {
+                last - ticks
+            };
+            TICKS_OFFSET.fetch_add(skipped as u64, Ordering::Relaxed);
+        }
+        let ticks = TICKS_OFFSET.load(Ordering::Relaxed) + ticks as u64;
+        let instant = ticks * PIT_MAX_DIVISOR as u64 + clocks as u64;
+        // While IRQ0 is pending the counter has wrapped but the tick is not yet counted,
+        // which would read up to a period early.
+        Instant(LAST_INSTANT.fetch_max(instant, Ordering::Relaxed).max(instant))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().saturating_duration_since(*self)
+    }
+
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.saturating_duration_since(earlier)
+    }
+
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        self.0.checked_sub(earlier.0).map(clocks_to_duration)
+    }
+
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier).unwrap_or(Duration::ZERO)
+    }
+
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_add(duration_to_clocks(duration)?).map(Instant)
+    }
+
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_sub(duration_to_clocks(duration)?).map(Instant)
+    }
+}
+
+fn clocks_to_duration(clocks: u64) -> Duration {
+    let nanos = clocks as u128 * NANOS_PER_SEC / PIT_HZ as u128;
+    Duration::new((nanos / NANOS_PER_SEC) as u64, (nanos % NANOS_PER_SEC) as u32)
+}
+
+fn duration_to_clocks(duration: Duration) -> Option<u64> {
+    (duration.as_nanos() * PIT_HZ as u128 / NANOS_PER_SEC).try_into().ok()
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Instant {
+        self.checked_add(rhs).expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, rhs: Duration) -> Instant {
+        self.checked_sub(rhs).expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
@@ -0,0 +1,135 @@
+use core::mem::transmute;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicU32, Ordering};
+use crate::*;
+use crate::isr::*;
+use crate::pit::*;
+
+pub const TIMER_IRQ: u8 = 0;
+
+/// The fastest the driver runs, about 18.6 kHz, so interrupts can't swamp the program.
+pub const TIMER_MIN_DIVISOR: u32 = 0x40;
+
+/// Called from IRQ0 at the programmed rate, on the driver's stack with interrupts disabled.
+pub type TimerHandler = fn();
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static VECTOR: AtomicU8 = AtomicU8::new(0);
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(null_mut());
+/// The divisor of the period channel 0 is counting.
+static DIVISOR: AtomicU32 = AtomicU32::new(PIT_MAX_DIVISOR);
+/// The divisor of the periods after it, which differs after [`TimerDriver::set_rate`].
+static NEXT_DIVISOR: AtomicU32 = AtomicU32::new(PIT_MAX_DIVISOR);
+/// PIT clocks of the current BIOS tick that passed by the last interrupt.
+static BIOS_PHASE: AtomicU32 = AtomicU32::new(0);
+static TICKS: AtomicU32 = AtomicU32::new(0);
+static OLD_HANDLER: IsrChain = IsrChain::new();
+static STACK: IsrStack = IsrStack::new();
+
+extern "C" fn timer_isr() -> u32 {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+    let handler = HANDLER.load(Ordering::Acquire);
+    if !handler.is_null() {
+        let handler: TimerHandler = unsafe { transmute::<*mut (), TimerHandler>(handler) };
+        handler();
+    }
+    // A period just ended, which is when mode 2 loads a divisor set since the last one.
+    let divisor = DIVISOR.swap(NEXT_DIVISOR.load(Ordering::Relaxed), Ordering::Relaxed);
+    let phase = BIOS_PHASE.load(Ordering::Relaxed) + divisor;
+    if phase >= PIT_MAX_DIVISOR {
+        // The old handler counts the BIOS tick and acknowledges the PIC.
+        BIOS_PHASE.store(phase - PIT_MAX_DIVISOR, Ordering::Relaxed);
+        1
+    } else {
+        BIOS_PHASE.store(phase, Ordering::Relaxed);
+        pic_eoi();
+        0
+    }
+}
+
+isr_entry!(timer_isr_entry, timer_isr, STACK, OLD_HANDLER);
+
+/// Owns the IRQ0 vector and PIT channel 0 while alive. Only one driver can be installed at a time.
+///
+/// The previous handler still runs at the BIOS rate of 18.2 Hz, so the BIOS tick count,
+/// the DOS clock and [`crate::time::Instant`] keep working.
+pub struct TimerDriver(());
+
+impl TimerDriver {
+    /// Programs channel 0 to about `hz`, from 18.2 Hz to [`PIT_HZ`]` / `[`TIMER_MIN_DIVISOR`],
+    /// and calls `handler` on every interrupt. Panics if `hz` is zero.
+    pub fn install(hz: u32, handler: TimerHandler) -> Result<TimerDriver, InstallErr> {
+        let divisor = timer_divisor(hz);
+        isr_init()?;
+        if INSTALLED.swap(true, Ordering::Acquire) { return Err(InstallErr::AlreadyInstalled); }
+        HANDLER.store(handler as *mut (), Ordering::Release);
+        BIOS_PHASE.store(0, Ordering::Relaxed);
+        TICKS.store(0, Ordering::Relaxed);
+        lock_data(&HANDLER);
+        lock_data(&DIVISOR);
+        lock_data(&NEXT_DIVISOR);
+        lock_data(&BIOS_PHASE);
+        lock_data(&TICKS);
+        lock_data(&OLD_HANDLER);
+        lock_data(&STACK);
+        let vector = irq_vector(TIMER_IRQ);
+        VECTOR.store(vector, Ordering::Relaxed);
+        OLD_HANDLER.set(int_21h_ah_35h_get_int(vector).es_ebx_int_handler);
+        int_21h_ah_25h_set_int(vector, PmFarPtr::new(code_selector(), timer_isr_entry as *const () as usize as u32));
+        DIVISOR.store(divisor, Ordering::Relaxed);
+        NEXT_DIVISOR.store(divisor, Ordering::Relaxed);
+        configure(PitChannel::Ch0, PitMode::RateGenerator, divisor);
+        Ok(TimerDriver(()))
+    }
+
+    pub fn set_handler(&self, handler: TimerHandler) {
+        HANDLER.store(handler as *mut (), Ordering::Release);
+    }
+
+    /// Changes the rate from the end of the current period, as mode 2 reloads the counter only
+    /// then. Panics if `hz` is zero.
+    pub fn set_rate(&self, hz: u32) {
+        let divisor = timer_divisor(hz);
+        // An interrupt between the two would adopt the divisor a period early.
+        without_interrupts(|| {
+            NEXT_DIVISOR.store(divisor, Ordering::Relaxed);
+            set_reload(PitChannel::Ch0, divisor);
+        });
+    }
+
+    /// The divisor last programmed, which the current period may still predate.
+    pub fn divisor(&self) -> u32 { NEXT_DIVISOR.load(Ordering::Relaxed) }
+
+    pub fn hz(&self) -> u32 { hz_for_divisor(self.divisor()) }
+
+    /// Interrupts since the driver was installed, wrapping.
+    pub fn ticks(&self) -> u32 { TICKS.load(Ordering::Relaxed) }
+}
+
+fn timer_divisor(hz: u32) -> u32 {
+    divisor_for_hz(hz).max(TIMER_MIN_DIVISOR)
+}
+
+/// The channel 0 divisor and the PIT clocks of the current BIOS tick elapsed at its last reload.
+pub(crate) fn ch0_period() -> (u32, u32) {
+    (DIVISOR.load(Ordering::Relaxed), BIOS_PHASE.load(Ordering::Relaxed))
+}
+
+/// Restores the BIOS rate and the original IRQ0 handler if the driver is installed.
+pub(crate) fn uninstall() {
+    if INSTALLED.load(Ordering::Acquire) {
+        DIVISOR.store(PIT_MAX_DIVISOR, Ordering::Relaxed);
+        NEXT_DIVISOR.store(PIT_MAX_DIVISOR, Ordering::Relaxed);
+        BIOS_PHASE.store(0, Ordering::Relaxed);
+        configure(PitChannel::Ch0, PitMode::SquareWave, PIT_MAX_DIVISOR);
+        int_21h_ah_25h_set_int(VECTOR.load(Ordering::Relaxed), OLD_HANDLER.get());
+        HANDLER.store(null_mut(), Ordering::Release);
+        INSTALLED.store(false, Ordering::Release);
+    }
+}
+
+impl Drop for TimerDriver {
+    fn drop(&mut self) {
+        uninstall();
+    }
+}
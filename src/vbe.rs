@@ -0,0 +1,479 @@
+#[cfg(dos)]
+use core::arch::asm;
+use core::cmp::min;
+use core::slice;
+use crate::*;
+use crate::far_mem::FarSlice;
+use crate::palette::Rgb6;
+use crate::xfer::XferBuf;
+
+pub const VBE_INFO_BLOCK_SIZE: usize = 512;
+pub const MODE_INFO_BLOCK_SIZE: usize = 256;
+
+pub const VBE_CAP_DAC_8BIT: u32 = 0x01;
+pub const VBE_CAP_NOT_VGA: u32 = 0x02;
+pub const VBE_CAP_BLANK_PALETTE: u32 = 0x04;
+
+pub const MODE_ATTR_SUPPORTED: u16 = 0x0001;
+pub const MODE_ATTR_TTY: u16 = 0x0004;
+pub const MODE_ATTR_COLOR: u16 = 0x0008;
+pub const MODE_ATTR_GRAPHICS: u16 = 0x0010;
+pub const MODE_ATTR_NOT_VGA: u16 = 0x0020;
+pub const MODE_ATTR_NO_WINDOWS: u16 = 0x0040;
+pub const MODE_ATTR_LFB: u16 = 0x0080;
+
+pub const WIN_ATTR_SUPPORTED: u8 = 0x01;
+pub const WIN_ATTR_READABLE: u8 = 0x02;
+pub const WIN_ATTR_WRITABLE: u8 = 0x04;
+
+const MAX_MODES: usize = 256;
+const MAX_OEM_STR: usize = 64;
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct OemStr {
+    bytes: [u8; MAX_OEM_STR],
+    len: u8,
+}
+
+impl OemStr {
+    fn read(ptr: RmFarPtr) -> Result<OemStr, AxErr> {
+        let mut s = OemStr { bytes: [0; MAX_OEM_STR], len: 0 };
+        if ptr.to_u32() == 0 { return Ok(s); }
+        let mem = FarSlice::rm_segment(ptr.segment)?;
+        let len = min(MAX_OEM_STR as u32, 0x10000 - ptr.offset as u32);
+        for i in 0 .. len {
+            let ch = mem.read_u8(ptr.offset as u32 + i);
+            if ch == 0 { break; }
+            s.bytes[i as usize] = ch;
+            s.len += 1;
+        }
+        Ok(s)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] { &self.bytes[.. self.len as usize] }
+}
+
+#[derive(Debug, Clone)]
+pub struct VbeInfoBlock {
+    pub signature: [u8; 4],
+    pub version: u16,
+    pub oem_string: OemStr,
+    pub capabilities: u32,
+    pub total_memory_64k: u16,
+    pub oem_software_rev: u16,
+    pub oem_vendor_name: OemStr,
+    pub oem_product_name: OemStr,
+    pub oem_product_rev: OemStr,
+    modes: [u16; MAX_MODES],
+    mode_count: u16,
+}
+
+impl VbeInfoBlock {
+    pub fn query(xfer: &XferBuf) -> Result<VbeInfoBlock, VbeErr> {
+        assert!(xfer.len() as usize >= VBE_INFO_BLOCK_SIZE, "transfer buffer is too small");
+        let mem = xfer.slice();
+        mem.slice(0, VBE_INFO_BLOCK_SIZE as u32).unwrap().fill(0);
+        mem.write(0, b"VBE2");
+        int_10h_ax_4F00h_controller_info(xfer.ptr())?;
+        let mut block = [0u8; VBE_INFO_BLOCK_SIZE];
+        mem.read(0, &mut block);
+        let signature = [block[0], block[1], block[2], block[3]];
+        if &signature != b"VESA" {
+            return Err(VbeErr::BadSignature(signature));
+        }
+        let version = u16_at(&block, 4);
+        let is_vbe2 = version >= 0x0200;
+        let oem_str = |offset: usize| -> Result<OemStr, VbeErr> {
+            OemStr::read(RmFarPtr::from_u32(u32_at(&block, offset))).map_err(VbeErr::Status)
+        };
+        let empty = OemStr { bytes: [0; MAX_OEM_STR], len: 0 };
+        let mut info = VbeInfoBlock {
+            signature,
+            version,
+            oem_string: oem_str(6)?,
+            capabilities: u32_at(&block, 10),
+            total_memory_64k: u16_at(&block, 18),
+            oem_software_rev: if is_vbe2 { u16_at(&block, 20) } else { 0 },
+            oem_vendor_name: if is_vbe2 { oem_str(22)? } else { empty },
+            oem_product_name: if is_vbe2 { oem_str(26)? } else { empty },
+            oem_product_rev: if is_vbe2 { oem_str(30)? } else { empty },
+            modes: [0; MAX_MODES],
+            mode_count: 0,
+        };
+        let list = RmFarPtr::from_u32(u32_at(&block, 14));
+        let list_mem = FarSlice::rm_segment(list.segment).map_err(VbeErr::Status)?;
+        for i in 0 .. MAX_MODES as u32 {
+            let offset = list.offset as u32 + 2 * i;
+            if offset + 2 > list_mem.len() { break; }
+            let mode = list_mem.read_u16(offset);
+            if mode == 0xFFFF { break; }
+            info.modes[i as usize] = mode;
+            info.mode_count += 1;
+        }
+        Ok(info)
+    }
+
+    pub fn total_memory(&self) -> u32 { (self.total_memory_64k as u32) << 16 }
+
+    pub fn modes(&self) -> slice::Iter<'_, u16> {
+        self.modes[.. self.mode_count as usize].iter()
+    }
+
+    pub fn mode_infos<'a>(&'a self, xfer: &'a XferBuf) -> ModeInfos<'a> {
+        ModeInfos { xfer, modes: self.modes() }
+    }
+
+    pub fn find_mode(&self, xfer: &XferBuf, width: u16, height: u16, bits_per_pixel: u8) -> Option<(u16, ModeInfoBlock)> {
+        self.mode_infos(xfer).find(|(_, info)| {
+            info.is_supported() && info.is_graphics()
+                && info.x_resolution == width && info.y_resolution == height
+                && info.bits_per_pixel == bits_per_pixel
+        })
+    }
+}
+
+pub struct ModeInfos<'a> {
+    xfer: &'a XferBuf,
+    modes: slice::Iter<'a, u16>,
+}
+
+impl<'a> Iterator for ModeInfos<'a> {
+    type Item = (u16, ModeInfoBlock);
+
+    fn next(&mut self) -> Option<(u16, ModeInfoBlock)> {
+        loop {
+            let mode = *self.modes.next()?;
+            if let Ok(info) = ModeInfoBlock::query(self.xfer, mode) {
+                return Some((mode, info));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.modes.size_hint().1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum MemoryModel {
+    Text,
+    Cga,
+    Hercules,
+    Planar,
+    PackedPixel,
+    NonChain4,
+    DirectColor,
+    Yuv,
+    Other(u8),
+}
+
+impl MemoryModel {
+    pub const fn from_u8(value: u8) -> MemoryModel {
+        match value {
+            0x00 => MemoryModel::Text,
+            0x01 => MemoryModel::Cga,
+            0x02 => MemoryModel::Hercules,
+            0x03 => MemoryModel::Planar,
+            0x04 => MemoryModel::PackedPixel,
+            0x05 => MemoryModel::NonChain4,
+            0x06 => MemoryModel::DirectColor,
+            0x07 => MemoryModel::Yuv,
+            value => MemoryModel::Other(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct ColorField {
+    pub mask_size: u8,
+    pub position: u8,
+}
+
+impl ColorField {
+    /// The field's bits in a pixel, tolerating sizes and positions past 32 from a broken BIOS.
+    pub const fn mask(self) -> u32 {
+        let bits = match 1u64.checked_shl(self.mask_size as u32) {
+            Some(bit) => bit - 1,
+            None => u64::MAX,
+        };
+        match bits.checked_shl(self.position as u32) {
+            Some(mask) => mask as u32,
+            None => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModeInfoBlock {
+    pub mode_attributes: u16,
+    pub win_a_attributes: u8,
+    pub win_b_attributes: u8,
+    pub win_granularity_kb: u16,
+    pub win_size_kb: u16,
+    pub win_a_segment: u16,
+    pub win_b_segment: u16,
+    pub win_func: RmFarPtr,
+    pub bytes_per_scan_line: u16,
+    pub x_resolution: u16,
+    pub y_resolution: u16,
+    pub x_char_size: u8,
+    pub y_char_size: u8,
+    pub number_of_planes: u8,
+    pub bits_per_pixel: u8,
+    pub number_of_banks: u8,
+    pub memory_model: MemoryModel,
+    pub bank_size_kb: u8,
+    pub number_of_image_pages: u8,
+    pub red: ColorField,
+    pub green: ColorField,
+    pub blue: ColorField,
+    pub reserved: ColorField,
+    pub direct_color_mode_info: u8,
+    pub phys_base_ptr: u32,
+    pub lin_bytes_per_scan_line: u16,
+    pub bnk_number_of_image_pages: u8,
+    pub lin_number_of_image_pages: u8,
+    pub lin_red: ColorField,
+    pub lin_green: ColorField,
+    pub lin_blue: ColorField,
+    pub lin_reserved: ColorField,
+    pub max_pixel_clock: u32,
+}
+
+impl ModeInfoBlock {
+    pub fn query(xfer: &XferBuf, mode: u16) -> Result<ModeInfoBlock, VbeErr> {
+        assert!(xfer.len() as usize >= MODE_INFO_BLOCK_SIZE, "transfer buffer is too small");
+        let mem = xfer.slice();
+        mem.slice(0, MODE_INFO_BLOCK_SIZE as u32).unwrap().fill(0);
+        int_10h_ax_4F01h_mode_info(mode, xfer.ptr())?;
+        let mut block = [0u8; MODE_INFO_BLOCK_SIZE];
+        mem.read(0, &mut block);
+        let field = |offset: usize| ColorField { mask_size: block[offset], position: block[offset + 1] };
+        Ok(ModeInfoBlock {
+            mode_attributes: u16_at(&block, 0),
+            win_a_attributes: block[2],
+            win_b_attributes: block[3],
+            win_granularity_kb: u16_at(&block, 4),
+            win_size_kb: u16_at(&block, 6),
+            win_a_segment: u16_at(&block, 8),
+            win_b_segment: u16_at(&block, 10),
+            win_func: RmFarPtr::from_u32(u32_at(&block, 12)),
+            bytes_per_scan_line: u16_at(&block, 16),
+            x_resolution: u16_at(&block, 18),
+            y_resolution: u16_at(&block, 20),
+            x_char_size: block[22],
+            y_char_size: block[23],
+            number_of_planes: block[24],
+            bits_per_pixel: block[25],
+            number_of_banks: block[26],
+            memory_model: MemoryModel::from_u8(block[27]),
+            bank_size_kb: block[28],
+            number_of_image_pages: block[29],
+            red: field(31),
+            green: field(33),
+            blue: field(35),
+            reserved: field(37),
+            direct_color_mode_info: block[39],
+            phys_base_ptr: u32_at(&block, 40),
+            lin_bytes_per_scan_line: u16_at(&block, 50),
+            bnk_number_of_image_pages: block[52],
+            lin_number_of_image_pages: block[53],
+            lin_red: field(54),
+            lin_green: field(56),
+            lin_blue: field(58),
+            lin_reserved: field(60),
+            max_pixel_clock: u32_at(&block, 62),
+        })
+    }
+
+    pub fn is_supported(&self) -> bool { self.mode_attributes & MODE_ATTR_SUPPORTED != 0 }
+
+    pub fn is_graphics(&self) -> bool { self.mode_attributes & MODE_ATTR_GRAPHICS != 0 }
+
+    pub fn has_lfb(&self) -> bool { self.mode_attributes & MODE_ATTR_LFB != 0 }
+
+    pub fn has_windows(&self) -> bool { self.mode_attributes & MODE_ATTR_NO_WINDOWS == 0 }
+
+    pub fn win_granularity(&self) -> u32 { (self.win_granularity_kb as u32) << 10 }
+
+    pub fn win_size(&self) -> u32 { (self.win_size_kb as u32) << 10 }
+
+    /// Selects the window the CPU can write through, preferring window A.
+    pub fn write_window(&self) -> Option<(u8, u16)> {
+        let writable = WIN_ATTR_SUPPORTED | WIN_ATTR_WRITABLE;
+        if self.win_a_attributes & writable == writable {
+            Some((0, self.win_a_segment))
+        } else if self.win_b_attributes & writable == writable {
+            Some((1, self.win_b_segment))
+        } else {
+            None
+        }
+    }
+}
+
+pub fn set_mode(mode: u16, lfb: bool, clear: bool) -> Result<(), VbeErr> {
+    let mut bx_mode = mode & 0x01FF;
+    if lfb { bx_mode |= VBE_MODE_LFB; }
+    if !clear { bx_mode |= VBE_MODE_NO_CLEAR; }
+    int_10h_ax_4F02h_set_mode(bx_mode)
+}
+
+pub fn mode() -> Result<u16, VbeErr> {
+    int_10h_ax_4F03h_mode().map(|m| m.bx_mode)
+}
+
+pub fn set_palette(xfer: &XferBuf, first: u8, colors: &[Rgb6], in_vretrace: bool) -> Result<(), VbeErr> {
+    assert!(first as usize + colors.len() <= 256);
+    let mut table = [0u8; 1024];
+    for (color, entry) in colors.iter().zip(table.chunks_exact_mut(4)) {
+        entry.copy_from_slice(&[color.b, color.g, color.r, 0]);
+    }
+    xfer.copy_in(0, &table[.. 4 * colors.len()]);
+    let (cx_count, dx_first) = (colors.len() as u16, first as u16);
+    if in_vretrace {
+        int_10h_ax_4F09h_bl_80h_set_palette_in_vretrace(cx_count, dx_first, xfer.ptr())
+    } else {
+        int_10h_ax_4F09h_bl_00h_set_palette(cx_count, dx_first, xfer.ptr())
+    }
+}
+
+pub fn palette(xfer: &XferBuf, first: u8, colors: &mut [Rgb6]) -> Result<(), VbeErr> {
+    assert!(first as usize + colors.len() <= 256);
+    assert!(xfer.len() as usize >= 4 * colors.len(), "transfer buffer is too small");
+    int_10h_ax_4F09h_bl_01h_palette(colors.len() as u16, first as u16, xfer.ptr())?;
+    let mut table = [0u8; 1024];
+    xfer.slice().read(0, &mut table[.. 4 * colors.len()]);
+    for (color, entry) in colors.iter_mut().zip(table.chunks_exact(4)) {
+        *color = Rgb6 { r: entry[2], g: entry[1], b: entry[0] };
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub enum PmInterfaceErr {
+    Vbe(VbeErr),
+    Selector(AxErr),
+    BufferTooSmall(u16),
+    NeedsMmio,
+}
+
+impl From<VbeErr> for PmInterfaceErr {
+    fn from(e: VbeErr) -> PmInterfaceErr { PmInterfaceErr::Vbe(e) }
+}
+
+/// The VBE 2.0 protected-mode interface, copied out of the video BIOS into `code`.
+///
+/// The copied functions run as 32-bit code through a near call, so `code` must be
+/// addressable through CS at the same offsets as through DS, as in the flat model.
+pub struct PmInterface<'a> {
+    code: &'a mut [u8],
+}
+
+impl<'a> PmInterface<'a> {
+    pub fn new(code: &'a mut [u8]) -> Result<PmInterface<'a>, PmInterfaceErr> {
+        let table = int_10h_ax_4F0Ah_bl_00h_pm_interface()?;
+        let len = table.cx_len as usize;
+        if code.len() < len { return Err(PmInterfaceErr::BufferTooSmall(table.cx_len)); }
+        let mem = FarSlice::rm_segment(table.es_di_table.segment).map_err(PmInterfaceErr::Selector)?;
+        mem.read(table.es_di_table.offset as u32, &mut code[.. len]);
+        let interface = PmInterface { code: &mut code[.. len] };
+        if interface.mmio_ranges().next().is_some() {
+            return Err(PmInterfaceErr::NeedsMmio);
+        }
+        Ok(interface)
+    }
+
+    fn entry(&self, index: usize) -> u16 { u16_at(self.code, 2 * index) }
+
+    /// I/O ports the interface functions access, for hosts that need them unprotected.
+    pub fn io_ports(&self) -> impl Iterator<Item = u16> + '_ {
+        let table = self.entry(3) as usize;
+        let mut offset = table;
+        core::iter::from_fn(move || {
+            if table == 0 || offset + 2 > self.code.len() { return None; }
+            let port = u16_at(self.code, offset);
+            if port == 0xFFFF { return None; }
+            offset += 2;
+            Some(port)
+        })
+    }
+
+    /// Memory-mapped ranges as `(base, len)` pairs; none are supported here.
+    pub fn mmio_ranges(&self) -> impl Iterator<Item = (u32, u16)> + '_ {
+        let table = self.entry(3) as usize;
+        let mut offset = table + 2 * self.io_ports().count() + 2;
+        core::iter::from_fn(move || {
+            if table == 0 || offset + 6 > self.code.len() { return None; }
+            if u16_at(self.code, offset) == 0xFFFF { return None; }
+            let range = (u32_at(self.code, offset), u16_at(self.code, offset + 4));
+            offset += 6;
+            Some(range)
+        })
+    }
+
+    fn proc(&self, index: usize) -> u32 {
+        self.code.as_ptr() as usize as u32 + self.entry(index) as u32
+    }
+
+    /// # Safety
+    ///
+    /// A VBE mode with windowed access must be active, and the interface must belong to
+    /// the adapter that set it.
+    pub unsafe fn set_window(&self, window: u8, position: u16) {
+        pm_call(self.proc(0), 0x4F05, window as u32, 0, position as u32, 0);
+    }
+
+    /// Sets the display start to a byte offset into video memory, which must be a multiple of 4.
+    ///
+    /// # Safety
+    ///
+    /// See [`PmInterface::set_window`].
+    pub unsafe fn set_display_start(&self, offset: u32, in_vretrace: bool) {
+        let start = offset >> 2;
+        let bl = if in_vretrace { 0x80 } else { 0x00 };
+        pm_call(self.proc(1), 0x4F07, bl, start & 0xFFFF, start >> 16, 0);
+    }
+
+    /// # Safety
+    ///
+    /// See [`PmInterface::set_window`].
+    pub unsafe fn set_palette(&self, first: u8, colors: &[Rgb6], in_vretrace: bool) {
+        assert!(first as usize + colors.len() <= 256);
+        let mut table = [0u8; 1024];
+        for (color, entry) in colors.iter().zip(table.chunks_exact_mut(4)) {
+            entry.copy_from_slice(&[color.b, color.g, color.r, 0]);
+        }
+        let bl = if in_vretrace { 0x80 } else { 0x00 };
+        pm_call(self.proc(2), 0x4F09, bl, colors.len() as u32, first as u32, table.as_ptr() as usize as u32);
+    }
+}
+
+#[cfg(not(dos))]
+#[allow(unused_variables)]
+unsafe fn pm_call(proc: u32, eax: u32, ebx: u32, ecx: u32, edx: u32, edi: u32) {
+    panic!("cfg(target_os=\"dos\")");
+}
+
+#[cfg(dos)]
+#[inline]
+unsafe fn pm_call(proc: u32, eax: u32, ebx: u32, ecx: u32, edx: u32, edi: u32) {
+    asm!(
+        "push esi",
+        "call {proc}",
+        "pop esi",
+        proc = in(reg) proc,
+        inlateout("eax") eax => _,
+        inlateout("ebx") ebx => _,
+        inlateout("ecx") ecx => _,
+        inlateout("edx") edx => _,
+        inlateout("edi") edi => _,
+    );
+}
@@ -0,0 +1,115 @@
+use crate::*;
+
+pub const VGA_ATTR_INDEX: u16 = 0x3C0;
+pub const VGA_MISC_OUTPUT_WRITE: u16 = 0x3C2;
+pub const VGA_SEQ_INDEX: u16 = 0x3C4;
+pub const VGA_SEQ_DATA: u16 = 0x3C5;
+pub const VGA_DAC_READ_INDEX: u16 = 0x3C7;
+pub const VGA_DAC_WRITE_INDEX: u16 = 0x3C8;
+pub const VGA_DAC_DATA: u16 = 0x3C9;
+pub const VGA_MISC_OUTPUT_READ: u16 = 0x3CC;
+pub const VGA_GC_INDEX: u16 = 0x3CE;
+pub const VGA_GC_DATA: u16 = 0x3CF;
+pub const VGA_CRTC_INDEX_COLOR: u16 = 0x3D4;
+pub const VGA_CRTC_INDEX_MONO: u16 = 0x3B4;
+pub const VGA_INPUT_STATUS_1_COLOR: u16 = 0x3DA;
+pub const VGA_INPUT_STATUS_1_MONO: u16 = 0x3BA;
+
+pub const INPUT_STATUS_1_DISPLAY_DISABLED: u8 = 0x01;
+pub const INPUT_STATUS_1_VRETRACE: u8 = 0x08;
+
+pub const SEQ_RESET: u8 = 0x00;
+pub const SEQ_MAP_MASK: u8 = 0x02;
+pub const SEQ_MEMORY_MODE: u8 = 0x04;
+
+pub const GC_READ_MAP_SELECT: u8 = 0x04;
+
+pub const CRTC_START_ADDRESS_HIGH: u8 = 0x0C;
+pub const CRTC_START_ADDRESS_LOW: u8 = 0x0D;
+pub const CRTC_VERTICAL_RETRACE_END: u8 = 0x11;
+pub const CRTC_UNDERLINE_LOCATION: u8 = 0x14;
+pub const CRTC_MODE_CONTROL: u8 = 0x17;
+
+pub const ATTR_HORIZONTAL_PEL_PANNING: u8 = 0x13;
+pub const ATTR_PALETTE_ADDRESS_SOURCE: u8 = 0x20;
+
+pub fn is_color() -> bool {
+    let misc = unsafe { port_in_u8(VGA_MISC_OUTPUT_READ) };
+    misc & 0x01 != 0
+}
+
+pub fn crtc_index_port() -> u16 {
+    if is_color() { VGA_CRTC_INDEX_COLOR } else { VGA_CRTC_INDEX_MONO }
+}
+
+pub fn input_status_1_port() -> u16 {
+    if is_color() { VGA_INPUT_STATUS_1_COLOR } else { VGA_INPUT_STATUS_1_MONO }
+}
+
+pub fn in_vretrace() -> bool {
+    let status = unsafe { port_in_u8(input_status_1_port()) };
+    status & INPUT_STATUS_1_VRETRACE != 0
+}
+
+pub fn wait_vretrace() {
+    let port = input_status_1_port();
+    while unsafe { port_in_u8(port) } & INPUT_STATUS_1_VRETRACE != 0 { }
+    while unsafe { port_in_u8(port) } & INPUT_STATUS_1_VRETRACE == 0 { }
+}
+
+pub fn wait_display_enable() {
+    let port = input_status_1_port();
+    while unsafe { port_in_u8(port) } & INPUT_STATUS_1_DISPLAY_DISABLED != 0 { }
+}
+
+pub fn seq_write(index: u8, value: u8) {
+    unsafe { port_out_u16(VGA_SEQ_INDEX, ((value as u16) << 8) | index as u16); }
+}
+
+pub fn seq_read(index: u8) -> u8 {
+    unsafe {
+        port_out_u8(VGA_SEQ_INDEX, index);
+        port_in_u8(VGA_SEQ_DATA)
+    }
+}
+
+pub fn gc_write(index: u8, value: u8) {
+    unsafe { port_out_u16(VGA_GC_INDEX, ((value as u16) << 8) | index as u16); }
+}
+
+pub fn gc_read(index: u8) -> u8 {
+    unsafe {
+        port_out_u8(VGA_GC_INDEX, index);
+        port_in_u8(VGA_GC_DATA)
+    }
+}
+
+pub fn crtc_write(index: u8, value: u8) {
+    unsafe { port_out_u16(crtc_index_port(), ((value as u16) << 8) | index as u16); }
+}
+
+pub fn crtc_read(index: u8) -> u8 {
+    let port = crtc_index_port();
+    unsafe {
+        port_out_u8(port, index);
+        port_in_u8(port + 1)
+    }
+}
+
+pub fn attr_write(index: u8, value: u8) {
+    // Reading input status 1 points the attribute flip-flop at the index.
+    unsafe {
+        port_in_u8(input_status_1_port());
+        port_out_u8(VGA_ATTR_INDEX, index | ATTR_PALETTE_ADDRESS_SOURCE);
+        port_out_u8(VGA_ATTR_INDEX, value);
+    }
+}
+
+pub fn set_start_address(offset: u16) {
+    crtc_write(CRTC_START_ADDRESS_HIGH, (offset >> 8) as u8);
+    crtc_write(CRTC_START_ADDRESS_LOW, offset as u8);
+}
+
+pub fn set_pel_panning(pixels: u8) {
+    attr_write(ATTR_HORIZONTAL_PEL_PANNING, pixels);
+}
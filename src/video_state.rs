@@ -0,0 +1,138 @@
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU8, Ordering};
+use crate::*;
+use crate::adapter::{Adapter, detect_adapter};
+use crate::palette::Palette;
+use crate::xfer::XferBuf;
+
+const VIDEO_STATE_ALL: u16 = VIDEO_STATE_HARDWARE | VIDEO_STATE_BIOS_DATA | VIDEO_STATE_DAC;
+
+const EMPTY: u8 = 0;
+const BUSY: u8 = 1;
+const SAVED: u8 = 2;
+
+#[derive(Debug, Clone)]
+pub enum VideoStateErr {
+    AlreadySaved,
+    Alloc(AllocErr),
+    Dpmi(DpmiErr),
+}
+
+impl From<AllocErr> for VideoStateErr {
+    fn from(e: AllocErr) -> VideoStateErr { VideoStateErr::Alloc(e) }
+}
+
+impl From<DpmiErr> for VideoStateErr {
+    fn from(e: DpmiErr) -> VideoStateErr { VideoStateErr::Dpmi(e) }
+}
+
+struct VideoState {
+    mode: u8,
+    vbe_mode: Option<u16>,
+    page: u8,
+    cursor: Cursor,
+    palette: Option<Palette>,
+    bios_state: Option<XferBuf>,
+}
+
+struct VideoStateCell(UnsafeCell<Option<VideoState>>);
+
+// Access is serialized through `STATE`.
+unsafe impl Sync for VideoStateCell { }
+
+static STATE: AtomicU8 = AtomicU8::new(EMPTY);
+static VIDEO_STATE: VideoStateCell = VideoStateCell(UnsafeCell::new(None));
+
+impl VideoState {
+    fn capture() -> Result<VideoState, VideoStateErr> {
+        let mode = int_10h_ah_0Fh_video_mode();
+        let vbe_mode = int_10h_ax_4F03h_mode().ok().map(|m| m.bx_mode & 0x3FFF).filter(|&m| m >= 0x100);
+        let cursor = int_10h_ah_03h_cursor(mode.bh_active_page);
+        let palette = match detect_adapter().adapter {
+            Adapter::Vga | Adapter::Mcga => Some(Palette::read()),
+            _ => None,
+        };
+        let bios_state = match int_10h_ax_1C00h_state_size(VIDEO_STATE_ALL)? {
+            Some(size) => {
+                let buf = XferBuf::new(size.bx_blocks * 4)?;
+                int_10h_ax_1C01h_save_state(VIDEO_STATE_ALL, buf.ptr())?;
+                Some(buf)
+            },
+            None => None,
+        };
+        Ok(VideoState {
+            mode: mode.al_mode & 0x7F,
+            vbe_mode,
+            page: mode.bh_active_page,
+            cursor,
+            palette,
+            bios_state,
+        })
+    }
+
+    fn restore(&self) {
+        if let Some(vbe_mode) = self.vbe_mode {
+            let _ = int_10h_ax_4F02h_set_mode(vbe_mode);
+        } else {
+            let in_vbe_mode = int_10h_ax_4F03h_mode().is_ok_and(|m| m.bx_mode & 0x3FFF >= 0x100);
+            if in_vbe_mode || int_10h_ah_0Fh_video_mode().al_mode & 0x7F != self.mode {
+                let _ = int_10h_ah_00h_set_video_mode(self.mode);
+            }
+        }
+        if let Some(buf) = &self.bios_state {
+            let _ = int_10h_ax_1C02h_restore_state(VIDEO_STATE_ALL, buf.ptr());
+        }
+        int_10h_ah_05h_set_video_active_page(self.page);
+        int_10h_ah_01h_set_cursor_shape(self.cursor.ch_start_line, self.cursor.cl_end_line);
+        int_10h_ah_02h_set_cursor_position(self.page, self.cursor.dh_row, self.cursor.dl_column);
+        if let Some(palette) = &self.palette {
+            palette.load();
+        }
+    }
+}
+
+/// Restores the video mode, page, cursor and palette captured by [`VideoStateGuard::save`].
+///
+/// Dropping the guard calls this. It is also meant to be called from the program's panic handler,
+/// since with `panic = "abort"` nothing is dropped, and before `int_21h_ah_4Ch_exit`, which never returns.
+/// Returns `false` if there was no saved state.
+pub fn restore_video_state() -> bool {
+    if STATE.compare_exchange(SAVED, BUSY, Ordering::Acquire, Ordering::Relaxed).is_err() {
+        return false;
+    }
+    let state = unsafe { (*VIDEO_STATE.0.get()).take() };
+    if let Some(state) = &state {
+        state.restore();
+    }
+    drop(state);
+    STATE.store(EMPTY, Ordering::Release);
+    true
+}
+
+/// Holds the saved video state and restores it on drop. Only one can exist at a time.
+pub struct VideoStateGuard(());
+
+impl VideoStateGuard {
+    pub fn save() -> Result<VideoStateGuard, VideoStateErr> {
+        if STATE.compare_exchange(EMPTY, BUSY, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return Err(VideoStateErr::AlreadySaved);
+        }
+        match VideoState::capture() {
+            Ok(state) => {
+                unsafe { *VIDEO_STATE.0.get() = Some(state); }
+                STATE.store(SAVED, Ordering::Release);
+                Ok(VideoStateGuard(()))
+            },
+            Err(e) => {
+                STATE.store(EMPTY, Ordering::Release);
+                Err(e)
+            },
+        }
+    }
+}
+
+impl Drop for VideoStateGuard {
+    fn drop(&mut self) {
+        restore_video_state();
+    }
+}
@@ -0,0 +1,192 @@
+use core::cmp::min;
+use core::mem::MaybeUninit;
+use crate::*;
+use crate::far_mem::{FarSlice, copy_from_far, copy_to_far};
+
+#[derive(Debug, Clone)]
+pub enum RmCallErr {
+    Dpmi(DpmiErr),
+    Dos(AxErr),
+}
+
+impl From<DpmiErr> for RmCallErr {
+    fn from(e: DpmiErr) -> RmCallErr { RmCallErr::Dpmi(e) }
+}
+
+impl From<AxErr> for RmCallErr {
+    fn from(e: AxErr) -> RmCallErr { RmCallErr::Dos(e) }
+}
+
+pub struct XferBuf {
+    alloc: RmAlloc,
+    len: u32,
+}
+
+impl XferBuf {
+    pub fn new(bx_paragraphs: u16) -> Result<XferBuf, AllocErr> {
+        let alloc = int_31h_ax_0100h_rm_alloc(bx_paragraphs)?;
+        Ok(XferBuf { alloc, len: (bx_paragraphs as u32) << 4 })
+    }
+
+    pub fn segment(&self) -> u16 { self.alloc.ax_segment }
+
+    pub fn selector(&self) -> u16 { self.alloc.dx_selector }
+
+    pub fn len(&self) -> u32 { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// The whole buffer, which the DOS block's own selector spans.
+    pub fn slice(&self) -> FarSlice {
+        unsafe { FarSlice::from_parts(self.alloc.dx_selector, 0, self.len) }.expect("transfer buffer selector")
+    }
+
+    pub fn ptr(&self) -> RmFarPtr {
+        RmFarPtr { segment: self.alloc.ax_segment, offset: 0 }
+    }
+
+    fn chunk_len(&self) -> usize {
+        min(self.len, 0xFFFF) as usize
+    }
+
+    pub fn copy_in(&self, offset: u16, src: &[u8]) {
+        assert!(offset as usize + src.len() <= self.len as usize, "transfer buffer overflow");
+        unsafe { copy_to_far(self.alloc.dx_selector, offset as u32, src); }
+    }
+
+    pub fn copy_out(&self, offset: u16, dest: &mut [MaybeUninit<u8>]) {
+        assert!(offset as usize + dest.len() <= self.len as usize, "transfer buffer overflow");
+        unsafe { copy_from_far(self.alloc.dx_selector, offset as u32, dest); }
+    }
+
+    fn int_21h(&self, regs: &mut RmRegs) -> Result<(), RmCallErr> {
+        regs.ds = self.alloc.ax_segment;
+        regs.es = self.alloc.ax_segment;
+        int_31h_ax_0300h_simulate_rm_int(0x21, regs)?;
+        if regs.flags & u16::from(CF) == 0 {
+            Ok(())
+        } else {
+            Err(RmCallErr::Dos(AxErr { ax_err: regs.eax as u16 }))
+        }
+    }
+
+    pub fn int_10h_ah_13h_write_string(
+        &self,
+        al_mode: u8,
+        bh_video_page: u8,
+        bl_attr: Attr,
+        dh_row: u8,
+        dl_column: u8,
+        str: &[u8]
+    ) -> Result<(), DpmiErr> {
+        self.copy_in(0, str);
+        let cx_len = if al_mode & WRITE_STRING_WITH_ATTRS != 0 { str.len() / 2 } else { str.len() };
+        int_10h_ah_13h_write_string(
+            al_mode, bh_video_page, bl_attr, u16::try_from(cx_len).unwrap(), dh_row, dl_column, self.ptr()
+        )
+    }
+
+    pub fn int_21h_ah_09h_out_str(&self, str_24h: &[u8]) -> Result<(), RmCallErr> {
+        assert!(str_24h.contains(&b'$'));
+        self.copy_in(0, str_24h);
+        let mut regs = RmRegs { eax: 0x0900, ..RmRegs::default() };
+        self.int_21h(&mut regs)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn int_21h_ah_0Ah_read_line(&self, buf: &mut LineBuf) -> Result<(), RmCallErr> {
+        assert!(self.len >= buf.max as u32 + 2, "transfer buffer is too small");
+        buf.len = 0;
+        self.copy_in(0, &[buf.max, 0]);
+        let mut regs = RmRegs { eax: 0x0A00, ..RmRegs::default() };
+        self.int_21h(&mut regs)?;
+        let mut header = [MaybeUninit::uninit(); 2];
+        self.copy_out(0, &mut header);
+        let len = min(unsafe { header[1].assume_init() }, buf.max - 1);
+        let line = unsafe { &mut *(&mut buf.buf[.. len as usize] as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.copy_out(2, line);
+        buf.len = len;
+        Ok(())
+    }
+
+    /// Leaves the 34-byte country information of the current country at the start of the buffer.
+    pub fn int_21h_ax_3800h_country_info(&self) -> Result<BxCountry, RmCallErr> {
+        let mut regs = RmRegs { eax: 0x3800, ..RmRegs::default() };
+        self.int_21h(&mut regs)?;
+        Ok(BxCountry { bx_country: regs.ebx as u16 })
+    }
+
+    /// Leaves the information `al_info_id` selects at the start of the buffer, with FFFFh
+    /// in `bx_code_page` and `dx_country` for the active ones.
+    pub fn int_21h_ah_65h_ext_country_info(
+        &self,
+        al_info_id: u8,
+        bx_code_page: u16,
+        dx_country: u16,
+        cx_len: u16
+    ) -> Result<(), RmCallErr> {
+        assert!(cx_len as u32 <= self.len, "transfer buffer overflow");
+        let mut regs = RmRegs {
+            eax: 0x6500 | al_info_id as u32,
+            ebx: bx_code_page as u32,
+            ecx: cx_len as u32,
+            edx: dx_country as u32,
+            ..RmRegs::default()
+        };
+        self.int_21h(&mut regs)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn int_21h_ah_3Dh_open(&self, path_z: &[u8], al_mode: u8) -> Result<AxHandle, RmCallErr> {
+        assert!(path_z.contains(&0));
+        self.copy_in(0, path_z);
+        let mut regs = RmRegs { eax: 0x3D00 | al_mode as u32, ..RmRegs::default() };
+        self.int_21h(&mut regs)?;
+        Ok(AxHandle { ax_handle: regs.eax as u16 })
+    }
+
+    #[allow(non_snake_case)]
+    pub fn int_21h_ah_3Fh_read(&self, bx_handle: u16, buf: &mut [MaybeUninit<u8>]) -> Result<usize, RmCallErr> {
+        let mut read = 0;
+        while read < buf.len() {
+            let chunk = min(buf.len() - read, self.chunk_len());
+            let mut regs = RmRegs {
+                eax: 0x3F00,
+                ebx: bx_handle as u32,
+                ecx: chunk as u32,
+                ..RmRegs::default()
+            };
+            self.int_21h(&mut regs)?;
+            let ax_read = regs.eax as u16 as usize;
+            self.copy_out(0, &mut buf[read .. read + ax_read]);
+            read += ax_read;
+            if ax_read < chunk { break; }
+        }
+        Ok(read)
+    }
+
+    pub fn int_21h_ah_40h_write(&self, bx_handle: u16, buf: &[u8]) -> Result<usize, RmCallErr> {
+        let mut written = 0;
+        while written < buf.len() {
+            let chunk = min(buf.len() - written, self.chunk_len());
+            self.copy_in(0, &buf[written .. written + chunk]);
+            let mut regs = RmRegs {
+                eax: 0x4000,
+                ebx: bx_handle as u32,
+                ecx: chunk as u32,
+                ..RmRegs::default()
+            };
+            self.int_21h(&mut regs)?;
+            let ax_written = regs.eax as u16 as usize;
+            written += ax_written;
+            if ax_written < chunk { break; }
+        }
+        Ok(written)
+    }
+}
+
+impl Drop for XferBuf {
+    fn drop(&mut self) {
+        let _ = int_31h_ax_0101h_rm_free(self.alloc.dx_selector);
+    }
+}